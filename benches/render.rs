@@ -0,0 +1,119 @@
+//! Redraws a Select of many options after a key press, in each
+//! [RenderMode].
+//!
+//! The app runs the UI and text layout without a GPU or a real window, so
+//! what's measured is spawning the nodes, laying out their text with Bevy's
+//! monospace default font and running flexbox over them. The prompt draws on
+//! its own thread, so only the update that shows its new frame is timed.
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bevy::audio::AudioPlugin;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::render::settings::{RenderCreation, WgpuSettings};
+use bevy::render::RenderPlugin;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+use bevy_inquire::*;
+use criterion::{criterion_group, criterion_main, Criterion};
+use inquire::Select;
+
+const OPTIONS: usize = 500;
+
+fn app(mode: RenderMode) -> App {
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .build()
+            .disable::<WinitPlugin>()
+            .disable::<LogPlugin>()
+            .disable::<AudioPlugin>()
+            .set(WindowPlugin {
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+                ..default()
+            })
+            .set(RenderPlugin {
+                render_creation: RenderCreation::Automatic(WgpuSettings {
+                    backends: None,
+                    ..default()
+                }),
+                ..default()
+            }),
+    )
+    .add_plugins(InquirePlugin::default());
+    app.finish();
+    app.cleanup();
+    app.world.resource_mut::<BevySettings>().mode = mode;
+    app.add_systems(Startup, |mut commands: Commands| {
+        commands.spawn(Camera2dBundle::default());
+        commands.prompt(|| {
+            let options: Vec<String> = (0..OPTIONS).map(|i| format!("Option {i}")).collect();
+            Select::new("Pick one", options).with_page_size(OPTIONS)
+        });
+    });
+    app.init_resource::<Redrawn>()
+        .add_systems(Last, note_redraws);
+    // Spawns the terminal, then waits for the prompt's first frame.
+    while !drawn(&mut app) {
+        app.update();
+        thread::sleep(Duration::from_millis(1));
+    }
+    app.update();
+    app
+}
+
+fn drawn(app: &mut App) -> bool {
+    let palette = TerminalPalette::default();
+    let mut terminals = app.world.query::<&BevyTerminal>();
+    terminals
+        .iter(&app.world)
+        .any(|terminal| terminal.cells(&palette).cells().iter().any(|cell| cell.ch == '?'))
+}
+
+/// Set on updates that draw a new frame of the prompt.
+#[derive(Resource, Default)]
+struct Redrawn(bool);
+
+fn note_redraws(terminals: Query<Ref<BevyTerminal>>, mut redrawn: ResMut<Redrawn>) {
+    redrawn.0 = terminals.iter().any(|terminal| terminal.is_changed());
+}
+
+/// Presses the down arrow, and returns how long the update showing the
+/// prompt's next frame took.
+fn press_down(app: &mut App) -> Duration {
+    for state in [ButtonState::Pressed, ButtonState::Released] {
+        app.world.send_event(KeyboardInput {
+            key_code: KeyCode::ArrowDown,
+            logical_key: Key::ArrowDown,
+            state,
+            window: Entity::PLACEHOLDER,
+        });
+    }
+    loop {
+        let start = Instant::now();
+        app.update();
+        let elapsed = start.elapsed();
+        if app.world.resource::<Redrawn>().0 {
+            return elapsed;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn redraw(c: &mut Criterion) {
+    let mut group = c.benchmark_group("redraw select");
+    group.sample_size(20);
+    for (name, mode) in [("spans", RenderMode::Spans), ("lines", RenderMode::Lines)] {
+        group.bench_function(name, |b| {
+            b.iter_custom(|iters| (0..iters).map(|_| press_down(&mut app(mode))).sum())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, redraw);
+criterion_main!(benches);
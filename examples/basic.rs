@@ -1,4 +1,5 @@
 use bevy_inquire::*;
+use inquire::Text;
 use bevy::{render::camera::ClearColorConfig, prelude::*, window::PresentMode};
 
 fn main() {
@@ -17,12 +18,13 @@ fn main() {
             }),
             ..default()
         }))
-        .add_systems(Update, bevy::window::close_on_esc)
+        .add_plugins(InquirePlugin::default())
         .add_systems(Startup, setup)
+        .add_systems(Update, print_answer)
         .run();
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup(mut commands: Commands, _asset_server: Res<AssetServer>) {
     // let settings = BevySettings {
     //     style: TextStyle {
     //         font: asset_server.load("fonts/DejaVuSansMono.ttf"),
@@ -46,5 +48,18 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
             ..default()
         },
         ..default()
-    }, BevyTerminal::default()));
+    }, BevyTerminal::default()))
+        .prompt(|| Text::new("What's your name?"));
+}
+
+fn print_answer(
+    mut completed: EventReader<PromptCompleted<String>>,
+    mut canceled: EventReader<PromptCanceled>,
+) {
+    for event in completed.read() {
+        info!("answer {:?}", event.answer);
+    }
+    for _ in canceled.read() {
+        info!("canceled");
+    }
 }
@@ -0,0 +1,61 @@
+use bevy_inquire::*;
+use bevy::prelude::*;
+use inquire::terminal::TerminalSize;
+use inquire::list_option::ListOption;
+use inquire::Select;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(InquirePlugin::default())
+        .add_systems(Startup, setup)
+        .add_systems(Update, (turn_screen, print_answer))
+        .run();
+}
+
+#[derive(Component)]
+struct Screen;
+
+fn setup(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 0.0, 3.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+    // The terminal draws into this image, which the screen's material shows.
+    let image = images.add(Image::default());
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Rectangle::new(2.0, 1.0)),
+            material: materials.add(StandardMaterial {
+                base_color_texture: Some(image.clone()),
+                unlit: true,
+                ..default()
+            }),
+            ..default()
+        },
+        Screen,
+    ));
+    commands
+        .spawn((
+            BevyTerminal::with_size(TerminalSize::new(40, 10)),
+            TerminalTarget::Image(image),
+        ))
+        .prompt(|| Select::new("Open the pod bay doors?", vec!["Yes", "No", "Ask again later"]));
+}
+
+fn turn_screen(time: Res<Time>, mut screens: Query<&mut Transform, With<Screen>>) {
+    for mut transform in &mut screens {
+        transform.rotation = Quat::from_rotation_y(time.elapsed_seconds().sin() * 0.4);
+    }
+}
+
+fn print_answer(mut completed: EventReader<PromptCompleted<ListOption<&'static str>>>) {
+    for event in completed.read() {
+        info!("answer {:?}", event.answer.value);
+    }
+}
@@ -0,0 +1,38 @@
+use bevy_inquire::*;
+use bevy::prelude::*;
+use inquire::{Confirm, Select, Text};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(InquirePlugin::default())
+        .add_systems(Startup, setup)
+        .run();
+}
+
+fn setup(mut commands: Commands, inquirer: Res<Inquirer>) {
+    commands.spawn(Camera2dBundle::default());
+    let terminal = commands.spawn((NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        ..default()
+    }, BevyTerminal::default())).id();
+
+    inquirer.spawn(move |inquirer| async move {
+        let Ok(name) = inquirer.ask(terminal, || Text::new("What's your name?")).await else {
+            return;
+        };
+        // Owned options, as futures holding borrowed ones can't be spawned.
+        let classes = || ["Rogue", "Mage", "Bard"].map(String::from).to_vec();
+        let Ok(class) = inquirer
+            .ask(terminal, move || Select::new("Pick a class", classes()))
+            .await else {
+            return;
+        };
+        if let Ok(true) = inquirer.ask(terminal, || Confirm::new("Set off?")).await {
+            info!("{} the {} sets off.", name, class.value);
+        }
+    });
+}
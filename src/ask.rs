@@ -0,0 +1,87 @@
+use std::future::Future;
+
+use async_channel::{Receiver, Sender};
+use bevy::prelude::*;
+use bevy::tasks::AsyncComputeTaskPool;
+use inquire::error::InquireResult;
+use inquire::InquireError;
+
+use crate::commands::insert_prompt;
+use crate::driver::{BevyPrompt, PromptRunner};
+
+struct PromptRequest {
+    terminal: Entity,
+    runner: PromptRunner,
+}
+
+/// Runs prompts from async code so multi-step wizards can be written as
+/// straight-line code.
+///
+/// ```ignore
+/// fn start(inquirer: Res<Inquirer>, terminal: Query<Entity, With<BevyTerminal>>) {
+///     let terminal = terminal.single();
+///     inquirer.spawn(move |inquirer| async move {
+///         let Ok(name) = inquirer.ask(terminal, || Text::new("Name?")).await else {
+///             return;
+///         };
+///         let class = inquirer
+///             .ask(terminal, || Select::new("Class?", vec!["Rogue", "Mage"]))
+///             .await;
+///         info!("{name} the {class:?}");
+///     });
+/// }
+/// ```
+#[derive(Resource, Clone)]
+pub struct Inquirer {
+    requests: Sender<PromptRequest>,
+}
+
+#[derive(Resource)]
+pub(crate) struct PromptRequests(Receiver<PromptRequest>);
+
+pub(crate) fn prompt_channel() -> (Inquirer, PromptRequests) {
+    let (requests, receiver) = async_channel::unbounded();
+    (Inquirer { requests }, PromptRequests(receiver))
+}
+
+impl Inquirer {
+    /// Runs the prompt built by `factory` on the `terminal` entity and
+    /// resolves with its answer. Resolves with
+    /// [InquireError::OperationInterrupted] if the terminal is despawned or
+    /// another prompt replaces this one before it finishes.
+    pub async fn ask<F, P>(&self, terminal: Entity, factory: F) -> InquireResult<P::Output>
+    where
+        F: Fn() -> P + Send + Sync + 'static,
+        P: BevyPrompt + 'static,
+    {
+        let (sender, receiver) = async_channel::bounded(1);
+        let runner = PromptRunner::with_responder(factory, Some(sender));
+        self.requests
+            .send(PromptRequest { terminal, runner })
+            .await
+            .map_err(|_| InquireError::OperationInterrupted)?;
+        receiver
+            .recv()
+            .await
+            .unwrap_or(Err(InquireError::OperationInterrupted))
+    }
+
+    /// Spawns `wizard` on the [AsyncComputeTaskPool].
+    pub fn spawn<W, Fut>(&self, wizard: W)
+    where
+        W: FnOnce(Inquirer) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        AsyncComputeTaskPool::get()
+            .spawn(wizard(self.clone()))
+            .detach();
+    }
+}
+
+pub(crate) fn receive_prompt_requests(mut commands: Commands, requests: Res<PromptRequests>) {
+    while let Ok(request) = requests.0.try_recv() {
+        if let Some(mut entity) = commands.get_entity(request.terminal) {
+            entity.add(insert_prompt(request.runner));
+        }
+    }
+}
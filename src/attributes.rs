@@ -0,0 +1,40 @@
+//! Text attributes the renderer draws.
+//!
+//! inquire's [Attributes] only have room for bold and italic, so the writer
+//! keeps its own [TextAttributes] on each span.
+use bitflags::bitflags;
+use inquire::ui::Attributes;
+
+bitflags! {
+    /// How a [Cell](crate::Cell) or span of text is drawn.
+    ///
+    /// Prompts can only ask for `BOLD` and `ITALIC`, through the
+    /// [Attributes] of their `RenderConfig`, as inquire has no way to ask
+    /// for the others. Those are only set by the terminal itself: selected
+    /// text is drawn in `REVERSE` and text being composed with an input
+    /// method is `UNDERLINE`d.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    pub struct TextAttributes: u8 {
+        /// Drawn with [BevySettings::bold_font](crate::BevySettings::bold_font).
+        const BOLD = 0b0000_0001;
+        /// Drawn with [BevySettings::italic_font](crate::BevySettings::italic_font).
+        const ITALIC = 0b0000_0010;
+        /// Draws a line under the text.
+        const UNDERLINE = 0b0000_0100;
+        /// Draws a line through the text.
+        const STRIKETHROUGH = 0b0000_1000;
+        /// Decreases the text intensity.
+        const DIM = 0b0001_0000;
+        /// Swaps the foreground and background colors.
+        const REVERSE = 0b0010_0000;
+    }
+}
+
+impl From<Attributes> for TextAttributes {
+    fn from(attributes: Attributes) -> Self {
+        let mut text = Self::empty();
+        text.set(Self::BOLD, attributes.contains(Attributes::BOLD));
+        text.set(Self::ITALIC, attributes.contains(Attributes::ITALIC));
+        text
+    }
+}
@@ -0,0 +1,215 @@
+use bevy::input::keyboard::{Key as LogicalKey, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use inquire::ui::{Key, KeyModifiers};
+
+use crate::focus::TerminalFocus;
+use crate::{from_input, BevyInput, BevyTerminal};
+
+/// Somewhere text can be copied to and pasted from.
+pub trait ClipboardProvider: Send + Sync + 'static {
+    fn get_text(&mut self) -> Option<String>;
+    fn set_text(&mut self, text: String);
+}
+
+/// A clipboard that only lives as long as the app, for tests and platforms
+/// without one.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryClipboard {
+    pub text: Option<String>,
+}
+
+impl ClipboardProvider for MemoryClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn set_text(&mut self, text: String) {
+        self.text = Some(text);
+    }
+}
+
+/// The operating system's clipboard.
+#[cfg(feature = "system-clipboard")]
+pub struct SystemClipboard(std::sync::Mutex<arboard::Clipboard>);
+
+#[cfg(feature = "system-clipboard")]
+impl SystemClipboard {
+    pub fn new() -> Result<Self, arboard::Error> {
+        Ok(Self(std::sync::Mutex::new(arboard::Clipboard::new()?)))
+    }
+}
+
+#[cfg(feature = "system-clipboard")]
+impl ClipboardProvider for SystemClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        let clipboard = self.0.get_mut().ok()?;
+        clipboard.get_text().ok()
+    }
+
+    fn set_text(&mut self, text: String) {
+        if let Ok(clipboard) = self.0.get_mut() {
+            if let Err(e) = clipboard.set_text(text) {
+                warn!("Could not copy to the clipboard: {}", e);
+            }
+        }
+    }
+}
+
+/// The clipboard Ctrl+V pastes from and Ctrl+C copies the selected text of
+/// the focused terminal to. Without a selection Ctrl+C goes to the prompt,
+/// which it interrupts.
+///
+/// Defaults to the [SystemClipboard] with the `system-clipboard` feature, and
+/// to a [MemoryClipboard] without it or if the system clipboard can't be
+/// opened.
+#[derive(Resource)]
+pub struct Clipboard(pub Box<dyn ClipboardProvider>);
+
+impl Clipboard {
+    pub fn new(provider: impl ClipboardProvider) -> Self {
+        Self(Box::new(provider))
+    }
+}
+
+impl Default for Clipboard {
+    fn default() -> Self {
+        #[cfg(feature = "system-clipboard")]
+        match SystemClipboard::new() {
+            Ok(clipboard) => return Self::new(clipboard),
+            Err(e) => warn!("Could not open the system clipboard: {}", e),
+        }
+        Self::new(MemoryClipboard::default())
+    }
+}
+
+enum Command {
+    Copy,
+    Paste,
+}
+
+fn command(key: &LogicalKey, mods: KeyModifiers, selected: bool) -> Option<Command> {
+    if !mods.contains(KeyModifiers::CONTROL) {
+        return None;
+    }
+    match key {
+        LogicalKey::Character(c) if selected && c.eq_ignore_ascii_case("c") => Some(Command::Copy),
+        LogicalKey::Character(c) if c.eq_ignore_ascii_case("v") => Some(Command::Paste),
+        _ => None,
+    }
+}
+
+/// Whether `key` copies or pastes rather than going to the prompt, on a
+/// terminal with text `selected` or not.
+pub(crate) fn is_clipboard_key(key: &LogicalKey, mods: KeyModifiers, selected: bool) -> bool {
+    command(key, mods, selected).is_some()
+}
+
+pub(crate) fn copy_and_paste(
+    mut presses: EventReader<KeyboardInput>,
+    input: Res<ButtonInput<KeyCode>>,
+    focus: Res<TerminalFocus>,
+    mut clipboard: ResMut<Clipboard>,
+    mut terminals: Query<(&BevyTerminal, &mut BevyInput)>,
+) {
+    let mods = from_input(&input);
+    let Some((terminal, mut input)) = focus.0.and_then(|id| terminals.get_mut(id).ok()) else {
+        return;
+    };
+    let selected = terminal.writer.selection.is_some();
+    for press in presses.read() {
+        if press.state != ButtonState::Pressed {
+            continue;
+        }
+        match command(&press.logical_key, mods, selected) {
+            Some(Command::Copy) => {
+                if let Some(text) = terminal.writer.selected_text() {
+                    clipboard.0.set_text(text);
+                }
+            }
+            Some(Command::Paste) => {
+                let Some(text) = clipboard.0.get_text() else {
+                    continue;
+                };
+                let keys = text
+                    .chars()
+                    .filter(|c| !c.is_control())
+                    .map(|c| Key::Char(c, KeyModifiers::NONE));
+                input.keys.extend(keys);
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use bevy::input::keyboard::{Key as LogicalKey, KeyboardInput};
+    use bevy::input::ButtonState;
+    use bevy::prelude::*;
+    use inquire::ui::{Key, KeyModifiers};
+
+    use super::*;
+    use crate::text_style_adapter::CursorPos;
+
+    fn app(clipboard: MemoryClipboard) -> (App, Entity) {
+        let mut app = App::new();
+        app.add_event::<KeyboardInput>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(Clipboard::new(clipboard))
+            .add_systems(Update, copy_and_paste);
+        let id = app.world.spawn((BevyTerminal::default(), BevyInput::default())).id();
+        app.insert_resource(TerminalFocus(Some(id)));
+        (app, id)
+    }
+
+    fn press_ctrl(app: &mut App, key_code: KeyCode, c: &str) {
+        app.world.resource_mut::<ButtonInput<KeyCode>>().press(KeyCode::ControlLeft);
+        app.world.send_event(KeyboardInput {
+            key_code,
+            logical_key: LogicalKey::Character(c.into()),
+            state: ButtonState::Pressed,
+            window: Entity::PLACEHOLDER,
+        });
+        app.update();
+    }
+
+    #[test]
+    fn paste_queues_text_as_keys() {
+        let (mut app, id) = app(MemoryClipboard {
+            text: Some("a\tb\u{e9}".to_string()),
+        });
+        press_ctrl(&mut app, KeyCode::KeyV, "v");
+        let keys: Vec<Key> = app.world.get::<BevyInput>(id).unwrap().keys.iter().copied().collect();
+        let none = KeyModifiers::NONE;
+        assert_eq!(keys, [Key::Char('a', none), Key::Char('b', none), Key::Char('\u{e9}', none)]);
+    }
+
+    #[test]
+    fn copy_writes_the_selection() {
+        let (mut app, id) = app(MemoryClipboard::default());
+        let mut terminal = app.world.get_mut::<BevyTerminal>(id).unwrap();
+        write!(terminal.writer, "hello\nworld").unwrap();
+        terminal.writer.commit();
+        terminal.writer.selection = Some((CursorPos { line: 0, column: 3 }, CursorPos { line: 1, column: 1 }));
+        press_ctrl(&mut app, KeyCode::KeyC, "c");
+        let copied = app.world.resource_mut::<Clipboard>().0.get_text();
+        assert_eq!(copied.as_deref(), Some("lo\nw"));
+        assert!(app.world.get::<BevyInput>(id).unwrap().keys.is_empty());
+    }
+
+    #[test]
+    fn copy_needs_a_selection() {
+        let (mut app, _) = app(MemoryClipboard::default());
+        press_ctrl(&mut app, KeyCode::KeyC, "c");
+        assert!(app.world.resource_mut::<Clipboard>().0.get_text().is_none());
+
+        let ctrl = KeyModifiers::CONTROL;
+        let c = LogicalKey::Character("c".into());
+        assert!(!is_clipboard_key(&c, ctrl, false));
+        assert!(is_clipboard_key(&c, ctrl, true));
+        assert!(is_clipboard_key(&LogicalKey::Character("v".into()), ctrl, false));
+    }
+}
@@ -0,0 +1,103 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::ecs::world::EntityWorldMut;
+use bevy::prelude::*;
+
+use crate::driver::{BevyPrompt, PromptRunner};
+use crate::{BevyInput, BevyTerminal, TerminalTarget};
+
+/// Runs inquire prompts on [BevyTerminal]s from [Commands].
+///
+/// ```ignore
+/// commands.prompt(|| Text::new("Name?"));
+/// commands.entity(terminal).prompt(|| Select::new("Class?", vec!["Rogue", "Mage"]));
+/// ```
+///
+/// Prompts are given as factories because inquire prompts are not `Send`, so
+/// they are built on the thread they run on; see [PromptRunner].
+pub trait InquireCommandsExt {
+    /// Runs the prompt built by `factory` on a terminal. If the entity has no
+    /// UI node or [BevyTerminal], a column [NodeBundle] and a default terminal
+    /// are added; terminals with a [TerminalTarget] get no node. A prompt
+    /// already running on the terminal is replaced.
+    ///
+    /// Panics when the command is applied if the [PromptCompleted] event of
+    /// the prompt's output is not registered.
+    ///
+    /// [PromptCompleted]: crate::PromptCompleted
+    fn prompt<F, P>(&mut self, factory: F) -> EntityCommands<'_>
+    where
+        F: Fn() -> P + Send + Sync + 'static,
+        P: BevyPrompt + 'static;
+}
+
+impl InquireCommandsExt for Commands<'_, '_> {
+    fn prompt<F, P>(&mut self, factory: F) -> EntityCommands<'_>
+    where
+        F: Fn() -> P + Send + Sync + 'static,
+        P: BevyPrompt + 'static,
+    {
+        let mut entity = self.spawn_empty();
+        entity.add(insert_prompt(PromptRunner::new(factory)));
+        entity
+    }
+}
+
+impl InquireCommandsExt for EntityCommands<'_> {
+    fn prompt<F, P>(&mut self, factory: F) -> EntityCommands<'_>
+    where
+        F: Fn() -> P + Send + Sync + 'static,
+        P: BevyPrompt + 'static,
+    {
+        self.add(insert_prompt(PromptRunner::new(factory)));
+        self.reborrow()
+    }
+}
+
+pub(crate) fn insert_prompt(runner: PromptRunner) -> impl FnOnce(EntityWorldMut) + Send + 'static {
+    move |mut entity: EntityWorldMut| {
+        runner.check_completed_event(entity.world());
+        if !entity.contains::<Node>() && !entity.contains::<TerminalTarget>() {
+            entity.insert(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            });
+        }
+        if !entity.contains::<BevyTerminal>() {
+            entity.insert(BevyTerminal::default());
+        }
+        // Keys sent before the prompt started are not meant for it.
+        if let Some(mut input) = entity.get_mut::<BevyInput>() {
+            input.keys.clear();
+        }
+        entity.insert(runner);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use inquire::CustomType;
+
+    use super::*;
+    use crate::InquireAppExt;
+
+    #[test]
+    #[should_panic(expected = "app.add_prompt_output::<u32>()")]
+    fn unregistered_outputs_panic() {
+        let mut world = World::new();
+        let runner = PromptRunner::new(|| CustomType::<u32>::new("Age?"));
+        insert_prompt(runner)(world.spawn_empty());
+    }
+
+    #[test]
+    fn registered_outputs_start() {
+        let mut app = App::new();
+        app.add_prompt_output::<u32>();
+        let runner = PromptRunner::new(|| CustomType::<u32>::new("Age?"));
+        let id = app.world.spawn_empty().id();
+        insert_prompt(runner)(app.world.entity_mut(id));
+        assert!(app.world.get::<PromptRunner>(id).is_some());
+    }
+}
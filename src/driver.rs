@@ -0,0 +1,458 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use async_channel::{Receiver, Sender};
+use bevy::prelude::*;
+use inquire::error::InquireResult;
+use inquire::terminal::TerminalSize;
+use inquire::list_option::ListOption;
+use inquire::ui::{Backend, InputReader, Key, RenderConfig};
+use inquire::{Confirm, CustomType, InquireError, MultiSelect, Password, Select, Text};
+
+use crate::events::{PromptCanceled, PromptCompleted};
+use crate::text_style_adapter::Frame;
+use crate::{BevyInput, BevyTerminal};
+
+/// Returned by an [InputReader] once every key received so far has been read.
+/// Unlike [InquireError::OperationCanceled] it means the prompt is still
+/// waiting for input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputExhausted;
+
+impl Display for InputExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no more keys available")
+    }
+}
+
+impl Error for InputExhausted {}
+
+pub(crate) fn input_exhausted() -> InquireError {
+    InquireError::Custom(Box::new(InputExhausted))
+}
+
+/// An inquire prompt that can be run on a [BevyTerminal].
+pub trait BevyPrompt {
+    type Output: Send + Sync + 'static;
+
+    fn prompt_with<I: InputReader>(
+        self,
+        input: I,
+        terminal: &mut BevyTerminal,
+    ) -> InquireResult<Self::Output>;
+
+    /// The prefixes list prompts draw before their options, used to map
+    /// clicks on options to keys.
+    fn option_prefixes(&self) -> Option<OptionPrefixes> {
+        None
+    }
+
+    /// Whether the prompt is answered with `y` or `n`, so gamepads bind
+    /// [GamepadBindings::confirm_buttons](crate::GamepadBindings::confirm_buttons).
+    fn answers_yes_or_no(&self) -> bool {
+        false
+    }
+}
+
+/// The prefixes a list prompt draws before its options. Options that are
+/// neither highlighted nor at the edge of a page are prefixed with a space.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionPrefixes {
+    pub highlighted: String,
+    pub scroll_up: String,
+    pub scroll_down: String,
+}
+
+impl From<&RenderConfig<'_>> for OptionPrefixes {
+    fn from(config: &RenderConfig<'_>) -> Self {
+        Self {
+            highlighted: config.highlighted_option_prefix.content.to_string(),
+            scroll_up: config.scroll_up_prefix.content.to_string(),
+            scroll_down: config.scroll_down_prefix.content.to_string(),
+        }
+    }
+}
+
+impl<'a> BevyPrompt for Text<'a> {
+    type Output = String;
+
+    fn prompt_with<I: InputReader>(self, input: I, terminal: &mut BevyTerminal) -> InquireResult<String> {
+        let mut backend = Backend::new(input, terminal, self.render_config)?;
+        self.prompt_with_backend(&mut backend)
+    }
+}
+
+impl<'a> BevyPrompt for Password<'a> {
+    type Output = String;
+
+    fn prompt_with<I: InputReader>(self, input: I, terminal: &mut BevyTerminal) -> InquireResult<String> {
+        let mut backend = Backend::new(input, terminal, self.render_config)?;
+        self.prompt_with_backend(&mut backend)
+    }
+}
+
+impl<'a> BevyPrompt for Confirm<'a> {
+    type Output = bool;
+
+    fn prompt_with<I: InputReader>(self, input: I, terminal: &mut BevyTerminal) -> InquireResult<bool> {
+        let mut backend = Backend::new(input, terminal, self.render_config)?;
+        self.prompt_with_backend(&mut backend)
+    }
+
+    fn answers_yes_or_no(&self) -> bool {
+        true
+    }
+}
+
+impl<'a, T> BevyPrompt for CustomType<'a, T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type Output = T;
+
+    fn prompt_with<I: InputReader>(self, input: I, terminal: &mut BevyTerminal) -> InquireResult<T> {
+        let mut backend = Backend::new(input, terminal, self.render_config)?;
+        self.prompt_with_backend(&mut backend)
+    }
+}
+
+impl<'a, T> BevyPrompt for Select<'a, T>
+where
+    T: Display + Send + Sync + 'static,
+{
+    type Output = ListOption<T>;
+
+    fn prompt_with<I: InputReader>(
+        self,
+        input: I,
+        terminal: &mut BevyTerminal,
+    ) -> InquireResult<ListOption<T>> {
+        let mut backend = Backend::new(input, terminal, self.render_config)?;
+        self.prompt_with_backend(&mut backend)
+    }
+
+    fn option_prefixes(&self) -> Option<OptionPrefixes> {
+        Some((&self.render_config).into())
+    }
+}
+
+impl<'a, T> BevyPrompt for MultiSelect<'a, T>
+where
+    T: Display + Send + Sync + 'static,
+{
+    type Output = Vec<ListOption<T>>;
+
+    fn prompt_with<I: InputReader>(
+        self,
+        input: I,
+        terminal: &mut BevyTerminal,
+    ) -> InquireResult<Vec<ListOption<T>>> {
+        let mut backend = Backend::new(input, terminal, self.render_config)?;
+        self.prompt_with_backend(&mut backend)
+    }
+
+    fn option_prefixes(&self) -> Option<OptionPrefixes> {
+        Some((&self.render_config).into())
+    }
+}
+
+/// Feeds a prompt the keys its [PromptRunner] receives, blocking its worker
+/// thread until there are some.
+struct ChannelInput(Receiver<Key>);
+
+impl InputReader for ChannelInput {
+    fn read_key(&mut self) -> InquireResult<Key> {
+        // The channel closes when the runner is removed.
+        self.0
+            .recv_blocking()
+            .map_err(|_| InquireError::OperationInterrupted)
+    }
+}
+
+/// Sent from a prompt's worker thread to its [PromptRunner].
+enum Update {
+    Frame(Frame),
+    /// The prompt is finished; queues its [PromptCompleted] or
+    /// [PromptCanceled] event, if any.
+    Finished(Box<dyn FnOnce(Entity, &mut Commands) + Send>),
+}
+
+/// Connects the terminal a prompt draws on, on its worker thread, to the
+/// [BevyTerminal] of the runner's entity.
+pub(crate) struct WorkerLink {
+    size: Arc<Mutex<TerminalSize>>,
+    updates: Sender<Update>,
+}
+
+impl WorkerLink {
+    pub(crate) fn size(&self) -> TerminalSize {
+        *self.size.lock().unwrap()
+    }
+
+    pub(crate) fn send_frame(&self, frame: Frame) {
+        let _ = self.updates.try_send(Update::Frame(frame));
+    }
+}
+
+/// Runs `factory`'s prompt until it finishes and sends what it should do
+/// then.
+fn run_prompt<F, P>(
+    factory: F,
+    keys: Receiver<Key>,
+    link: WorkerLink,
+    responder: Option<Sender<InquireResult<P::Output>>>,
+) where
+    F: Fn() -> P,
+    P: BevyPrompt,
+{
+    let updates = link.updates.clone();
+    let mut terminal = BevyTerminal {
+        size: link.size(),
+        writer: default(),
+        link: Some(link),
+    };
+    let result = factory().prompt_with(ChannelInput(keys), &mut terminal);
+    let finish: Box<dyn FnOnce(Entity, &mut Commands) + Send> = match responder {
+        Some(responder) => {
+            let _ = responder.try_send(result);
+            Box::new(|_, _| {})
+        }
+        None => Box::new(move |id, commands| match result {
+            Ok(answer) => commands.add(move |world: &mut World| {
+                world.send_event(PromptCompleted { terminal: id, answer });
+            }),
+            Err(e) => {
+                if !matches!(
+                    e,
+                    InquireError::OperationCanceled | InquireError::OperationInterrupted
+                ) {
+                    error!("Prompt on {:?} failed: {}", id, e);
+                }
+                commands.add(move |world: &mut World| {
+                    world.send_event(PromptCanceled { terminal: id });
+                });
+            }
+        }),
+    };
+    let _ = updates.try_send(Update::Finished(finish));
+}
+
+/// Runs a prompt on the [BevyTerminal] of the same entity without blocking
+/// the game loop.
+///
+/// Inquire prompts block in [InputReader::read_key], so each prompt runs on a
+/// thread of its own, built from its factory once the terminal's UI node has
+/// been laid out. The keys the terminal receives are sent to that thread and
+/// the frames the prompt draws are sent back and shown a frame later. Once
+/// the prompt is submitted or canceled a [PromptCompleted] or [PromptCanceled]
+/// event is sent and the runner is removed. Prompts started with
+/// [Inquirer::ask](crate::Inquirer::ask) resolve their future instead of
+/// sending events.
+///
+/// Removing the runner, or despawning its entity, interrupts the prompt. A
+/// resized terminal is drawn at its new size from the prompt's next frame
+/// on. Keys sent to a terminal without a runner are dropped, so they can't
+/// submit the next prompt.
+#[derive(Component)]
+pub struct PromptRunner {
+    /// Spawns the prompt's thread. Taken once the terminal has its size.
+    start: Option<Box<dyn FnOnce() + Send + Sync>>,
+    keys: Sender<Key>,
+    updates: Receiver<Update>,
+    size: Arc<Mutex<TerminalSize>>,
+    pub(crate) option_prefixes: Option<OptionPrefixes>,
+    pub(crate) yes_no: bool,
+    /// Whether the app can send the prompt's [PromptCompleted] event, and
+    /// the name of its output, for runners that send events.
+    completed_event: Option<(fn(&World) -> bool, &'static str)>,
+}
+
+impl PromptRunner {
+    pub fn new<F, P>(factory: F) -> Self
+    where
+        F: Fn() -> P + Send + Sync + 'static,
+        P: BevyPrompt + 'static,
+    {
+        Self::with_responder(factory, None)
+    }
+
+    pub(crate) fn with_responder<F, P>(
+        factory: F,
+        responder: Option<Sender<InquireResult<P::Output>>>,
+    ) -> Self
+    where
+        F: Fn() -> P + Send + Sync + 'static,
+        P: BevyPrompt + 'static,
+    {
+        let (keys, key_receiver) = async_channel::unbounded();
+        let (update_sender, updates) = async_channel::unbounded();
+        let size = Arc::new(Mutex::new(BevyTerminal::default().size));
+        let link = WorkerLink {
+            size: size.clone(),
+            updates: update_sender,
+        };
+        let prompt = factory();
+        let completed_event = responder.is_none().then_some((
+            (|world: &World| world.contains_resource::<Events<PromptCompleted<P::Output>>>())
+                as fn(&World) -> bool,
+            std::any::type_name::<P::Output>(),
+        ));
+        Self {
+            option_prefixes: prompt.option_prefixes(),
+            yes_no: prompt.answers_yes_or_no(),
+            completed_event,
+            start: Some(Box::new(move || {
+                thread::Builder::new()
+                    .name("inquire prompt".to_string())
+                    .spawn(move || run_prompt(factory, key_receiver, link, responder))
+                    .expect("failed to spawn a prompt thread");
+            })),
+            keys,
+            updates,
+            size,
+        }
+    }
+
+    /// Panics if the prompt's answer can't be sent as a [PromptCompleted]
+    /// event, which would otherwise be dropped once the prompt is submitted.
+    pub(crate) fn check_completed_event(&self, world: &World) {
+        if let Some((registered, output)) = self.completed_event {
+            assert!(
+                registered(world),
+                "PromptCompleted<{output}> is not registered; add it with \
+                 `app.add_prompt_output::<{output}>()`"
+            );
+        }
+    }
+}
+
+pub(crate) fn drive_prompts(
+    mut commands: Commands,
+    mut runners: Query<(
+        Entity,
+        &mut PromptRunner,
+        &mut BevyTerminal,
+        &mut BevyInput,
+        Option<Ref<Node>>,
+    )>,
+    mut idle: Query<&mut BevyInput, Without<PromptRunner>>,
+) {
+    for mut input in &mut idle {
+        if !input.keys.is_empty() {
+            input.keys.clear();
+        }
+    }
+    for (id, mut runner, mut terminal, mut input, node) in &mut runners {
+        let runner = &mut *runner;
+        *runner.size.lock().unwrap() = terminal.size;
+        if runner.start.is_some() {
+            // A new node is only sized by the UI layout at the end of the
+            // frame, and the terminal fitted to it on the next one.
+            if node.is_some_and(|node| node.is_added()) {
+                continue;
+            }
+            if let Some(start) = runner.start.take() {
+                start();
+            }
+        }
+        for key in input.keys.drain(..) {
+            let _ = runner.keys.try_send(key);
+        }
+        while let Ok(update) = runner.updates.try_recv() {
+            match update {
+                Update::Frame(frame) => terminal.writer.show(frame),
+                Update::Finished(finish) => {
+                    finish(id, &mut commands);
+                    commands.entity(id).remove::<PromptRunner>();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::event::ManualEventReader;
+    use inquire::ui::KeyModifiers;
+
+    use super::*;
+
+    fn app() -> (App, Entity) {
+        let mut app = App::new();
+        app.add_event::<PromptCompleted<String>>()
+            .add_event::<PromptCanceled>()
+            .add_systems(Update, drive_prompts);
+        let id = app
+            .world
+            .spawn((
+                BevyTerminal::default(),
+                BevyInput::default(),
+                PromptRunner::new(|| Text::new("Name?")),
+            ))
+            .id();
+        (app, id)
+    }
+
+    fn send_keys(app: &mut App, id: Entity, keys: impl IntoIterator<Item = Key>) {
+        app.world.get_mut::<BevyInput>(id).unwrap().keys.extend(keys);
+    }
+
+    /// Updates `app` until `done` holds, giving the prompt's thread time to
+    /// catch up.
+    fn update_until(app: &mut App, mut done: impl FnMut(&mut App) -> bool) -> bool {
+        for _ in 0..400 {
+            app.update();
+            if done(app) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    fn first_event<T: Event + Clone>(app: &mut App) -> Option<T> {
+        let mut reader = ManualEventReader::<T>::default();
+        let mut event = None;
+        update_until(app, |app| {
+            event = reader.read(app.world.resource::<Events<T>>()).next().cloned();
+            event.is_some()
+        });
+        event
+    }
+
+    #[test]
+    fn text_prompt_waits_for_keys_then_completes() {
+        let (mut app, id) = app();
+        let none = KeyModifiers::NONE;
+        send_keys(&mut app, id, [Key::Char('a', none), Key::Char('b', none)]);
+        let drawn = update_until(&mut app, |app| {
+            let terminal = app.world.get::<BevyTerminal>(id).unwrap();
+            terminal.writer.frame_lines()[0].contains("Name? ab")
+        });
+        assert!(drawn);
+        for _ in 0..3 {
+            app.update();
+        }
+        assert!(app.world.get::<PromptRunner>(id).is_some());
+        assert!(app.world.resource::<Events<PromptCompleted<String>>>().is_empty());
+
+        send_keys(&mut app, id, [Key::Enter]);
+        let completed = first_event::<PromptCompleted<String>>(&mut app).unwrap();
+        assert_eq!((completed.terminal, completed.answer.as_str()), (id, "ab"));
+        app.update();
+        assert!(app.world.get::<PromptRunner>(id).is_none());
+    }
+
+    #[test]
+    fn escape_cancels_the_prompt() {
+        let (mut app, id) = app();
+        send_keys(&mut app, id, [Key::Escape]);
+        let canceled = first_event::<PromptCanceled>(&mut app).unwrap();
+        assert_eq!(canceled.terminal, id);
+        assert!(app.world.resource::<Events<PromptCompleted<String>>>().is_empty());
+    }
+}
@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+
+/// Sent when the prompt running on the `terminal` entity is submitted.
+///
+/// `T` is the prompt's output: `String` for `Text` and `Password`, `bool` for
+/// `Confirm`, `ListOption<T>` for `Select` and `Vec<ListOption<T>>` for
+/// `MultiSelect`. Output types other than the ones [InquirePlugin] registers
+/// must be added with [InquireAppExt::add_prompt_output], or starting their
+/// prompts panics.
+///
+/// [InquirePlugin]: crate::InquirePlugin
+#[derive(Event, Debug, Clone)]
+pub struct PromptCompleted<T> {
+    pub terminal: Entity,
+    pub answer: T,
+}
+
+/// Sent when the prompt running on the `terminal` entity is canceled,
+/// interrupted or fails.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptCanceled {
+    pub terminal: Entity,
+}
+
+pub trait InquireAppExt {
+    /// Registers [PromptCompleted] for prompts whose output is `T`.
+    fn add_prompt_output<T: Send + Sync + 'static>(&mut self) -> &mut Self;
+}
+
+impl InquireAppExt for App {
+    fn add_prompt_output<T: Send + Sync + 'static>(&mut self) -> &mut Self {
+        self.add_event::<PromptCompleted<T>>()
+    }
+}
@@ -0,0 +1,111 @@
+use std::cmp::Ordering;
+
+use bevy::input::keyboard::{Key as LogicalKey, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use inquire::ui::KeyModifiers;
+
+use crate::{from_input, BevyTerminal};
+
+/// The [BevyTerminal] that receives keyboard input.
+///
+/// Clicking a terminal focuses it, and Ctrl+Tab and Ctrl+Shift+Tab cycle
+/// through the terminals from top to bottom, left to right. Plain Tab is left
+/// to the prompt, which uses it for autocompletion. When nothing is focused,
+/// or the focused terminal is despawned, the first terminal is focused.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TerminalFocus(pub Option<Entity>);
+
+impl TerminalFocus {
+    pub fn is_focused(&self, terminal: Entity) -> bool {
+        self.0 == Some(terminal)
+    }
+}
+
+/// Sent when [TerminalFocus] moves to another terminal.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TerminalFocusChanged {
+    pub previous: Option<Entity>,
+    pub focused: Option<Entity>,
+}
+
+/// Whether `key` cycles the focus rather than going to the prompt.
+pub(crate) fn is_cycle_key(key: &LogicalKey, mods: KeyModifiers) -> bool {
+    *key == LogicalKey::Tab && mods.contains(KeyModifiers::CONTROL)
+}
+
+type Terminals<'w, 's> = Query<'w, 's, (Entity, Option<&'static GlobalTransform>), With<BevyTerminal>>;
+
+/// Terminals in focus order.
+fn focus_order(terminals: &Terminals) -> Vec<Entity> {
+    let mut order: Vec<(Entity, Vec3)> = terminals
+        .iter()
+        .map(|(id, transform)| (id, transform.map_or(Vec3::ZERO, |t| t.translation())))
+        .collect();
+    order.sort_by(|(a_id, a), (b_id, b)| {
+        a.y.partial_cmp(&b.y)
+            .unwrap_or(Ordering::Equal)
+            .then(a.x.partial_cmp(&b.x).unwrap_or(Ordering::Equal))
+            .then(a_id.cmp(b_id))
+    });
+    order.into_iter().map(|(id, _)| id).collect()
+}
+
+pub(crate) fn add_interaction(
+    mut commands: Commands,
+    terminals: Query<Entity, (With<BevyTerminal>, Without<Interaction>)>,
+) {
+    for id in &terminals {
+        commands.entity(id).insert(Interaction::default());
+    }
+}
+
+pub(crate) fn update_focus(
+    mut focus: ResMut<TerminalFocus>,
+    mut presses: EventReader<KeyboardInput>,
+    input: Res<ButtonInput<KeyCode>>,
+    clicked: Query<(Entity, &Interaction), (Changed<Interaction>, With<BevyTerminal>)>,
+    terminals: Terminals,
+) {
+    let order = focus_order(&terminals);
+    let mut focused = focus.0.filter(|id| order.contains(id));
+    for (id, interaction) in &clicked {
+        if *interaction == Interaction::Pressed {
+            focused = Some(id);
+        }
+    }
+    let mods = from_input(&input);
+    for press in presses.read() {
+        if press.state != ButtonState::Pressed || !is_cycle_key(&press.logical_key, mods) {
+            continue;
+        }
+        let Some(i) = focused.and_then(|id| order.iter().position(|&o| o == id)) else {
+            focused = order.first().copied();
+            continue;
+        };
+        focused = if mods.contains(KeyModifiers::SHIFT) {
+            order.get((i + order.len() - 1) % order.len()).copied()
+        } else {
+            order.get((i + 1) % order.len()).copied()
+        };
+    }
+    let focused = focused.or_else(|| order.first().copied());
+    // Only touch the resource when it changes, so change detection works.
+    if focus.0 != focused {
+        focus.0 = focused;
+    }
+}
+
+pub(crate) fn send_focus_changes(
+    focus: Res<TerminalFocus>,
+    mut previous: Local<Option<Entity>>,
+    mut events: EventWriter<TerminalFocusChanged>,
+) {
+    if focus.0 != *previous {
+        events.send(TerminalFocusChanged {
+            previous: *previous,
+            focused: focus.0,
+        });
+        *previous = focus.0;
+    }
+}
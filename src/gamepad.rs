@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use bevy::input::gamepad::{GamepadAxisType, GamepadButtonType};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use inquire::ui::{Key, KeyModifiers};
+
+use crate::driver::PromptRunner;
+use crate::focus::TerminalFocus;
+use crate::input::KeyRepeat;
+use crate::BevyInput;
+
+/// Keys sent to the focused terminal by gamepad buttons and the left stick.
+///
+/// By default the D-pad and left stick are the arrow keys, South is Enter,
+/// East is Escape and West is space, which toggles options of a
+/// MultiSelect. Directions held down repeat like keys, see [KeyRepeat].
+///
+/// While a Confirm prompt runs, `confirm_buttons` are bound too: North and
+/// Select type `y` and `n`, to answer it either way before confirming it with
+/// South, which alone takes the default answer.
+#[derive(Resource, Debug, Clone)]
+pub struct GamepadBindings {
+    pub enabled: bool,
+    pub buttons: HashMap<GamepadButtonType, Key>,
+    /// Buttons bound only while a yes or no prompt runs, as other prompts
+    /// take characters as text or as a filter.
+    pub confirm_buttons: HashMap<GamepadButtonType, Key>,
+    /// Whether the left stick acts as the arrow keys.
+    pub left_stick: bool,
+    /// How far the stick has to be tilted to count as pressed.
+    pub stick_threshold: f32,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        let none = KeyModifiers::NONE;
+        Self {
+            enabled: true,
+            buttons: HashMap::from_iter([
+                (GamepadButtonType::DPadUp, Key::Up(none)),
+                (GamepadButtonType::DPadDown, Key::Down(none)),
+                (GamepadButtonType::DPadLeft, Key::Left(none)),
+                (GamepadButtonType::DPadRight, Key::Right(none)),
+                (GamepadButtonType::South, Key::Enter),
+                (GamepadButtonType::East, Key::Escape),
+                (GamepadButtonType::West, Key::Char(' ', none)),
+            ]),
+            confirm_buttons: HashMap::from_iter([
+                (GamepadButtonType::North, Key::Char('y', none)),
+                (GamepadButtonType::Select, Key::Char('n', none)),
+            ]),
+            left_stick: true,
+            stick_threshold: 0.5,
+        }
+    }
+}
+
+fn is_direction(key: Key) -> bool {
+    matches!(key, Key::Up(_) | Key::Down(_) | Key::Left(_) | Key::Right(_))
+}
+
+/// The direction key a gamepad is held in, and the time left until it
+/// repeats.
+#[derive(Default)]
+pub(crate) struct HeldDirections(HashMap<Gamepad, (Key, Duration)>);
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn gamepad_to_keys(
+    bindings: Res<GamepadBindings>,
+    repeat: Res<KeyRepeat>,
+    time: Res<Time<Real>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    focus: Res<TerminalFocus>,
+    mut held: Local<HeldDirections>,
+    mut inputs: Query<(&mut BevyInput, Option<&PromptRunner>)>,
+) {
+    if !bindings.enabled {
+        return;
+    }
+    let yes_no = focus
+        .0
+        .and_then(|id| inputs.get(id).ok())
+        .and_then(|(_, runner)| runner)
+        .is_some_and(|runner| runner.yes_no);
+    let confirm_buttons = bindings.confirm_buttons.iter().filter(|_| yes_no);
+    let mut keys = vec![];
+    for gamepad in gamepads.iter() {
+        let queued = keys.len();
+        let mut direction = None;
+        for (&button_type, &key) in bindings.buttons.iter().chain(confirm_buttons.clone()) {
+            let button = GamepadButton::new(gamepad, button_type);
+            if buttons.just_pressed(button) {
+                keys.push(key);
+            }
+            if is_direction(key) && buttons.pressed(button) {
+                direction = Some(key);
+            }
+        }
+        if bindings.left_stick && direction.is_none() {
+            direction = stick_direction(&axes, gamepad, bindings.stick_threshold);
+        }
+        let Some(key) = direction else {
+            held.0.remove(&gamepad);
+            continue;
+        };
+        match held.0.get_mut(&gamepad) {
+            Some((held_key, wait)) if *held_key == key => {
+                repeat.tick(key, wait, time.delta(), &mut keys);
+            }
+            _ => {
+                // A D-pad press was queued above, a stick tilt wasn't.
+                if !keys[queued..].contains(&key) {
+                    keys.push(key);
+                }
+                held.0.insert(gamepad, (key, repeat.delay));
+            }
+        }
+    }
+    if keys.is_empty() {
+        return;
+    }
+    if let Some((mut input, _)) = focus.0.and_then(|id| inputs.get_mut(id).ok()) {
+        input.keys.extend(keys);
+    }
+}
+
+fn stick_direction(axes: &Axis<GamepadAxis>, gamepad: Gamepad, threshold: f32) -> Option<Key> {
+    let x = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))?;
+    let y = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))?;
+    let none = KeyModifiers::NONE;
+    if x.abs().max(y.abs()) < threshold {
+        None
+    } else if y.abs() >= x.abs() {
+        Some(if y > 0.0 { Key::Up(none) } else { Key::Down(none) })
+    } else {
+        Some(if x > 0.0 { Key::Right(none) } else { Key::Left(none) })
+    }
+}
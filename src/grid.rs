@@ -0,0 +1,370 @@
+use ab_glyph::{point, Font as _, ScaleFont};
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::utils::HashMap;
+use inquire::terminal::TerminalSize;
+
+use crate::attributes::TextAttributes;
+use crate::palette::TerminalPalette;
+use crate::size::cell_size;
+use crate::text_style_adapter::{colors, BevySettings, LineCache, RenderMode, Span};
+use crate::BevyTerminal;
+
+/// A character on the grid of a [CellBuffer], with its colors resolved from
+/// a [TerminalPalette].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub fg: Color,
+    pub bg: Color,
+    pub attributes: TextAttributes,
+}
+
+/// A terminal's last frame as a fixed grid of lines of cells. Text past the
+/// last column or line is cut off.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellBuffer {
+    size: TerminalSize,
+    cells: Vec<Cell>,
+}
+
+impl CellBuffer {
+    /// A buffer of `size` filled with `blank`.
+    pub fn new(size: TerminalSize, blank: Cell) -> Self {
+        let len = size.width() as usize * size.height() as usize;
+        Self {
+            size,
+            cells: vec![blank; len],
+        }
+    }
+
+    pub(crate) fn from_lines(
+        size: TerminalSize,
+        lines: Vec<Vec<Span>>,
+        palette: &TerminalPalette,
+    ) -> Self {
+        let mut buffer = Self::new(
+            size,
+            Cell {
+                ch: ' ',
+                fg: palette.foreground,
+                bg: palette.background,
+                attributes: TextAttributes::empty(),
+            },
+        );
+        let width = size.width() as usize;
+        for (line, spans) in lines.into_iter().take(size.height() as usize).enumerate() {
+            let chars = spans.iter().flat_map(|span| {
+                let (fg, bg) = colors(span.style, palette);
+                span.content.chars().filter(|&c| c != '\n').map(move |ch| Cell {
+                    ch,
+                    fg,
+                    bg: bg.unwrap_or(palette.background),
+                    attributes: span.style.attributes,
+                })
+            });
+            let row = &mut buffer.cells[line * width..(line + 1) * width];
+            for (slot, cell) in row.iter_mut().zip(chars) {
+                *slot = cell;
+            }
+        }
+        buffer
+    }
+
+    pub fn size(&self) -> TerminalSize {
+        self.size
+    }
+
+    /// The cell at `column` on `line`, if it is on the grid.
+    pub fn get(&self, column: usize, line: usize) -> Option<&Cell> {
+        let width = self.size.width() as usize;
+        if column >= width {
+            return None;
+        }
+        self.cells.get(line * width + column)
+    }
+
+    /// All cells, line by line.
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+}
+
+/// Coverage masks of glyphs rasterized to fill a cell, by font and
+/// character.
+#[derive(Resource, Default)]
+pub(crate) struct GlyphAtlas {
+    cell: UVec2,
+    font_size: f32,
+    glyphs: HashMap<(AssetId<Font>, char), Vec<u8>>,
+}
+
+impl GlyphAtlas {
+    /// Forgets all glyphs if they were rasterized for another cell or font
+    /// size.
+    fn resize(&mut self, cell: UVec2, font_size: f32) {
+        if self.cell != cell || self.font_size != font_size {
+            self.cell = cell;
+            self.font_size = font_size;
+            self.glyphs.clear();
+        }
+    }
+
+    fn glyph(&mut self, id: AssetId<Font>, font: &Font, ch: char) -> &[u8] {
+        let (cell, font_size) = (self.cell, self.font_size);
+        self.glyphs
+            .entry((id, ch))
+            .or_insert_with(|| rasterize(font, ch, font_size, cell))
+    }
+}
+
+/// Draws `ch` on the baseline of a `cell` sized mask. Whatever sticks out of
+/// the cell is cut off, so box drawing characters join up with their
+/// neighbours.
+fn rasterize(font: &Font, ch: char, font_size: f32, cell: UVec2) -> Vec<u8> {
+    let mut mask = vec![0; (cell.x * cell.y) as usize];
+    let scaled = font.font.as_scaled(font_size);
+    let mut glyph = scaled.scaled_glyph(ch);
+    glyph.position = point(0.0, scaled.ascent());
+    let Some(outlined) = font.font.outline_glyph(glyph) else {
+        return mask;
+    };
+    let bounds = outlined.px_bounds();
+    outlined.draw(|x, y, coverage| {
+        let px = bounds.min.x as i32 + x as i32;
+        let py = bounds.min.y as i32 + y as i32;
+        if (0..cell.x as i32).contains(&px) && (0..cell.y as i32).contains(&py) {
+            mask[(py as u32 * cell.x + px as u32) as usize] = (coverage * 255.0) as u8;
+        }
+    });
+    mask
+}
+
+/// Draws a terminal outside of the UI, for screens in the game world.
+///
+/// Such terminals are drawn like [RenderMode::Grid] whatever the
+/// [BevySettings::mode], and take keyboard, gamepad and clipboard input like
+/// the others once focused, see [TerminalFocus](crate::TerminalFocus).
+/// Mouse and touch input are left to the game. They are not fitted to a
+/// node, so their size is the one they were made with.
+#[derive(Component, Debug, Clone)]
+pub enum TerminalTarget {
+    /// A sprite on the terminal's entity, one pixel per world unit. Spawn it
+    /// with a `SpatialBundle` for it to be visible.
+    World,
+    /// An image that is kept up to date with the terminal, for use in a
+    /// material or anywhere else.
+    Image(Handle<Image>),
+}
+
+/// The image a terminal is drawn into in [RenderMode::Grid] or for a
+/// [TerminalTarget], and the cells drawn into it.
+#[derive(Component)]
+pub(crate) struct TerminalGrid {
+    buffer: CellBuffer,
+    cell: UVec2,
+    image: Handle<Image>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render_grids(
+    mut commands: Commands,
+    settings: Res<BevySettings>,
+    fonts: Res<Assets<Font>>,
+    mut font_events: EventReader<AssetEvent<Font>>,
+    mut images: ResMut<Assets<Image>>,
+    mut atlas: ResMut<GlyphAtlas>,
+    default_palette: Res<TerminalPalette>,
+    palettes: Res<Assets<TerminalPalette>>,
+    mut terminals: Query<(
+        Entity,
+        &mut BevyTerminal,
+        Option<&Handle<TerminalPalette>>,
+        Option<&TerminalTarget>,
+        Option<&mut TerminalGrid>,
+    )>,
+) {
+    let rebuild = settings.is_changed() || font_events.read().count() > 0;
+    // Cells can only be sized once the font is loaded.
+    let Some(cell) = cell_size(&settings, &fonts) else {
+        return;
+    };
+    let cell = cell.ceil().as_uvec2();
+    atlas.resize(cell, settings.style.font_size);
+    for (id, mut terminal, handle, target, grid) in &mut terminals {
+        if target.is_none() && settings.mode != RenderMode::Grid {
+            continue;
+        }
+        if !terminal.writer.dirty && !rebuild {
+            continue;
+        }
+        terminal.writer.dirty = false;
+        let palette = handle
+            .and_then(|handle| palettes.get(handle))
+            .unwrap_or(&default_palette);
+        let buffer = terminal.cells(palette);
+        let fits = |grid: &Mut<TerminalGrid>| {
+            grid.cell == cell
+                && grid.buffer.size == buffer.size
+                && match target {
+                    Some(TerminalTarget::Image(image)) => grid.image == *image,
+                    _ => true,
+                }
+        };
+        if let Some(mut grid) = grid.filter(fits) {
+            if let Some(image) = images.get_mut(&grid.image) {
+                let previous = (!rebuild).then_some(&grid.buffer);
+                draw_cells(image, &buffer, previous, cell, &settings, &fonts, &mut atlas);
+                grid.buffer = buffer;
+                continue;
+            }
+        }
+
+        let size = UVec2::new(buffer.size.width() as u32, buffer.size.height() as u32) * cell;
+        let mut image = Image::new_fill(
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &palette.background.as_rgba_u8(),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        draw_cells(&mut image, &buffer, None, cell, &settings, &fonts, &mut atlas);
+        let image = match target {
+            Some(TerminalTarget::Image(handle)) => {
+                images.insert(handle, image);
+                handle.clone()
+            }
+            _ => images.add(image),
+        };
+        let mut entity = commands.entity(id);
+        entity.insert(TerminalGrid {
+            buffer,
+            cell,
+            image: image.clone(),
+        });
+        match target {
+            None => {
+                entity
+                    .despawn_descendants()
+                    .remove::<LineCache>()
+                    .insert(BackgroundColor(palette.background))
+                    .with_children(|parent| {
+                        parent.spawn(ImageBundle {
+                            style: Style {
+                                width: Val::Px(size.x as f32),
+                                height: Val::Px(size.y as f32),
+                                ..default()
+                            },
+                            image: UiImage::new(image),
+                            ..default()
+                        });
+                    });
+            }
+            Some(TerminalTarget::World) => {
+                entity.insert((Sprite::default(), image));
+            }
+            Some(TerminalTarget::Image(_)) => {}
+        }
+    }
+}
+
+/// Draws the cells of `buffer` that differ from `previous` into `image`.
+fn draw_cells(
+    image: &mut Image,
+    buffer: &CellBuffer,
+    previous: Option<&CellBuffer>,
+    cell: UVec2,
+    settings: &BevySettings,
+    fonts: &Assets<Font>,
+    atlas: &mut GlyphAtlas,
+) {
+    let width = buffer.size.width() as usize;
+    let stride = width * cell.x as usize;
+    let (cell_width, cell_height) = (cell.x as usize, cell.y as usize);
+    let thickness = (cell_height / 14).max(1);
+    for (i, c) in buffer.cells.iter().enumerate() {
+        if previous.is_some_and(|previous| previous.cells.get(i) == Some(c)) {
+            continue;
+        }
+        let font = settings.font(c.attributes);
+        let glyph = match fonts.get(&font) {
+            Some(loaded) if c.ch != ' ' => Some(atlas.glyph(font.id(), loaded, c.ch)),
+            _ => None,
+        };
+        let (fg, bg) = (c.fg.as_rgba_u8(), c.bg.as_rgba_u8());
+        let (left, top) = ((i % width) * cell_width, (i / width) * cell_height);
+        for y in 0..cell_height {
+            let decorated = (c.attributes.contains(TextAttributes::UNDERLINE)
+                && y >= cell_height - 2 * thickness
+                && y < cell_height - thickness)
+                || (c.attributes.contains(TextAttributes::STRIKETHROUGH)
+                    && (cell_height / 2..cell_height / 2 + thickness).contains(&y));
+            for x in 0..cell_width {
+                let coverage = match glyph {
+                    _ if decorated => 255,
+                    Some(glyph) => glyph[y * cell_width + x] as u32,
+                    None => 0,
+                };
+                let alpha = coverage * fg[3] as u32 / 255;
+                let at = ((top + y) * stride + left + x) * 4;
+                for (channel, (&fg, &bg)) in fg.iter().zip(&bg).enumerate() {
+                    let blended = (fg as u32 * alpha + bg as u32 * (255 - alpha)) / 255;
+                    image.data[at + channel] = blended as u8;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use inquire::ui::Color as InquireColor;
+
+    use super::*;
+    use crate::text_style_adapter::SpanStyle;
+
+    fn span(content: &str, style: SpanStyle) -> Span {
+        Span {
+            content: content.to_string(),
+            style,
+        }
+    }
+
+    #[test]
+    fn from_lines_clips_to_the_grid() {
+        let palette = TerminalPalette::vga();
+        let red = SpanStyle {
+            fg: Some(InquireColor::DarkRed),
+            ..default()
+        };
+        let blue = SpanStyle {
+            bg: Some(InquireColor::DarkBlue),
+            attributes: TextAttributes::UNDERLINE,
+            ..default()
+        };
+        let lines = vec![
+            vec![span("ab", red), span("cdef\n", blue)],
+            vec![span("x\n", SpanStyle::default())],
+            vec![span("cut off", SpanStyle::default())],
+        ];
+        let buffer = CellBuffer::from_lines(TerminalSize::new(4, 2), lines, &palette);
+        let text: String = buffer.cells().iter().map(|c| c.ch).collect();
+        assert_eq!(text, "abcdx   ");
+
+        let red = buffer.get(0, 0).unwrap();
+        assert_eq!((red.fg, red.bg), (palette.ansi[1], palette.background));
+        let blue = buffer.get(3, 0).unwrap();
+        assert_eq!((blue.fg, blue.bg), (palette.foreground, palette.ansi[4]));
+        assert_eq!(blue.attributes, TextAttributes::UNDERLINE);
+        let blank = buffer.get(1, 1).unwrap();
+        assert_eq!((blank.fg, blank.bg), (palette.foreground, palette.background));
+
+        assert_eq!(buffer.get(4, 0), None);
+        assert_eq!(buffer.get(0, 2), None);
+    }
+}
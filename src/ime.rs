@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use bevy::window::{Ime, PrimaryWindow, ReceivedCharacter};
+use inquire::ui::{Key, KeyModifiers};
+
+use crate::focus::TerminalFocus;
+use crate::mouse::{TerminalLine, TerminalSpan};
+use crate::{BevyInput, BevyTerminal};
+
+/// Input method support, for languages like Japanese and Chinese that compose
+/// text from several key presses.
+///
+/// While a terminal is focused the primary window's IME is enabled and its
+/// candidate box placed at the cursor. Text being composed is drawn
+/// underlined at the cursor, and keys go to the input method until the text
+/// is committed to the prompt.
+#[derive(Resource, Debug, Clone)]
+pub struct ImeSettings {
+    pub enabled: bool,
+}
+
+impl Default for ImeSettings {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+pub(crate) fn enable_ime(
+    settings: Res<ImeSettings>,
+    focus: Res<TerminalFocus>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let enabled = settings.enabled && focus.0.is_some();
+    for mut window in &mut windows {
+        if window.ime_enabled != enabled {
+            window.ime_enabled = enabled;
+        }
+    }
+}
+
+pub(crate) fn ime_to_keys(
+    mut events: EventReader<Ime>,
+    mut chars: EventReader<ReceivedCharacter>,
+    focus: Res<TerminalFocus>,
+    mut terminals: Query<(&mut BevyTerminal, &mut BevyInput)>,
+) {
+    // Some platforms send plain typing both as characters and as commits.
+    let received_chars = chars.read().count() > 0;
+    let Some((mut terminal, mut input)) = focus.0.and_then(|id| terminals.get_mut(id).ok())
+    else {
+        return;
+    };
+    // Composing ends with an empty preedit, which is followed by the commit.
+    let mut composed = input.composing;
+    let mut ended = false;
+    for event in events.read() {
+        match event {
+            Ime::Preedit { value, .. } => {
+                let preedit = (!value.is_empty()).then(|| value.clone());
+                ended |= input.composing && preedit.is_none();
+                composed |= preedit.is_some();
+                input.composing = preedit.is_some();
+                if terminal.writer.preedit != preedit {
+                    terminal.writer.preedit = preedit;
+                    terminal.writer.dirty = true;
+                }
+            }
+            Ime::Commit { value, .. } => {
+                if composed || !received_chars {
+                    let keys = value.chars().map(|c| Key::Char(c, KeyModifiers::NONE));
+                    input.keys.extend(keys);
+                }
+                ended |= composed;
+                composed = false;
+            }
+            Ime::Disabled { .. } => {
+                ended |= input.composing;
+                input.composing = false;
+                if terminal.writer.preedit.take().is_some() {
+                    terminal.writer.dirty = true;
+                }
+            }
+            Ime::Enabled { .. } => {}
+        }
+    }
+    if ended {
+        input.composition_ended = true;
+    }
+}
+
+/// Puts the input method's candidate box under the cursor of the focused
+/// terminal.
+pub(crate) fn place_ime(
+    focus: Res<TerminalFocus>,
+    terminals: Query<&BevyTerminal>,
+    lines: Query<(&TerminalLine, &Children)>,
+    spans: Query<(&TerminalSpan, &Node, &GlobalTransform)>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Some(id) = focus.0 else {
+        return;
+    };
+    let Some(cursor) = terminals.get(id).ok().and_then(|t| t.writer.frame_cursor) else {
+        return;
+    };
+    let Some((_, children)) = lines
+        .iter()
+        .find(|(line, _)| line.terminal == id && line.line == cursor.line)
+    else {
+        return;
+    };
+    let Some((span, node, transform)) = spans
+        .iter_many(children)
+        .find(|(span, _, _)| (span.column..span.column + span.len.max(1)).contains(&cursor.column))
+    else {
+        return;
+    };
+    let size = node.size();
+    let left = transform.translation().truncate() - size / 2.0;
+    let advance = size.x / span.len.max(1) as f32;
+    let position = Vec2::new(
+        left.x + advance * (cursor.column - span.column) as f32,
+        left.y + size.y,
+    );
+    for mut window in &mut windows {
+        if window.ime_enabled && window.ime_position != position {
+            window.ime_position = position;
+        }
+    }
+}
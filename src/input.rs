@@ -0,0 +1,265 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::input::keyboard::{Key as LogicalKey, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy::window::{ReceivedCharacter, WindowFocused};
+use inquire::ui::{Key, KeyModifiers};
+
+use crate::clipboard::is_clipboard_key;
+use crate::focus::{is_cycle_key, TerminalFocus};
+use crate::{from_input, BevyInput, BevyTerminal};
+
+/// Translates a Bevy logical key into an inquire [Key]. Returns `None` for
+/// keys inquire has no use for. Characters are returned as [Key::Char], but
+/// [collect_keys] prefers the text Bevy received for them.
+pub fn to_inquire_key(key: &LogicalKey, mods: KeyModifiers) -> Option<Key> {
+    Some(match key {
+        LogicalKey::Character(text) => Key::Char(text.chars().next()?, mods),
+        LogicalKey::Space => Key::Char(' ', mods),
+        LogicalKey::Enter => Key::Enter,
+        LogicalKey::Backspace => Key::Backspace,
+        // inquire has no back tab, so Shift+Tab is a Tab too.
+        LogicalKey::Tab => Key::Tab,
+        LogicalKey::Escape => Key::Escape,
+        LogicalKey::Delete => Key::Delete(mods),
+        LogicalKey::Home => Key::Home,
+        LogicalKey::End => Key::End,
+        LogicalKey::PageUp => Key::PageUp(mods),
+        LogicalKey::PageDown => Key::PageDown(mods),
+        LogicalKey::ArrowUp => Key::Up(mods),
+        LogicalKey::ArrowDown => Key::Down(mods),
+        LogicalKey::ArrowLeft => Key::Left(mods),
+        LogicalKey::ArrowRight => Key::Right(mods),
+        _ => return None,
+    })
+}
+
+/// Repeats the most recently pressed key while it is held.
+///
+/// Repeats are made here rather than taken from the OS, because Bevy doesn't
+/// deliver OS key repeats consistently across platforms; OS repeats are
+/// ignored.
+#[derive(Resource, Debug, Clone)]
+pub struct KeyRepeat {
+    pub enabled: bool,
+    /// How long a key is held before it starts repeating.
+    pub delay: Duration,
+    /// Time between repeats.
+    pub interval: Duration,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            delay: Duration::from_millis(500),
+            interval: Duration::from_millis(33),
+        }
+    }
+}
+
+/// Most repeats made in one frame, so a long frame doesn't flood the prompt
+/// with a held key.
+const MAX_REPEATS_PER_TICK: usize = 2;
+
+impl KeyRepeat {
+    /// Pushes a `key` held for another `elapsed` onto `keys` for each repeat
+    /// that is due, up to [MAX_REPEATS_PER_TICK]. `wait` is the time left
+    /// until the next repeat.
+    pub(crate) fn tick(&self, key: Key, wait: &mut Duration, elapsed: Duration, keys: &mut Vec<Key>) {
+        if !self.enabled {
+            return;
+        }
+        let interval = self.interval.max(Duration::from_millis(1));
+        let mut elapsed = elapsed;
+        let mut repeats = 0;
+        while elapsed >= *wait {
+            if repeats == MAX_REPEATS_PER_TICK {
+                // Repeats missed during a hitch are dropped.
+                *wait = interval;
+                return;
+            }
+            elapsed -= *wait;
+            *wait = interval;
+            keys.push(key);
+            repeats += 1;
+        }
+        *wait -= elapsed;
+    }
+}
+
+struct Repeating {
+    key_code: KeyCode,
+    key: Key,
+    /// Time left until the next repeat.
+    wait: Duration,
+}
+
+#[derive(Default)]
+pub(crate) struct HeldKeys {
+    pressed: HashSet<KeyCode>,
+    repeating: Option<Repeating>,
+    /// The terminal focused while the keys were held.
+    terminal: Option<Entity>,
+}
+
+/// Queues the keys pressed this frame, in the order they were pressed, on the
+/// focused terminal's [BevyInput].
+///
+/// Key presses give the order, while [ReceivedCharacter]s give the text with
+/// dead keys and compositions applied, so each character key press takes the
+/// next received character. Text received without a key press, like an OS
+/// level paste, is queued last.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn collect_keys(
+    mut presses: EventReader<KeyboardInput>,
+    mut chars: EventReader<ReceivedCharacter>,
+    input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time<Real>>,
+    repeat: Res<KeyRepeat>,
+    mut held: Local<HeldKeys>,
+    mut window_focus: EventReader<WindowFocused>,
+    focus: Res<TerminalFocus>,
+    mut inputs: Query<(&BevyTerminal, &mut BevyInput)>,
+) {
+    // Not every platform releases held keys when the window loses focus, and
+    // keys held for one terminal shouldn't repeat into the next.
+    let unfocused = window_focus.read().any(|event| !event.focused);
+    if unfocused || held.terminal != focus.0 {
+        *held = HeldKeys {
+            terminal: focus.0,
+            ..default()
+        };
+    }
+    let mods = from_input(&input);
+    let mut received: VecDeque<char> = chars
+        .read()
+        .flat_map(|event| event.char.chars())
+        .filter(|c| !c.is_control())
+        .collect();
+    let mut keys = vec![];
+    let mut pressed_now = false;
+    let focused = focus.0.and_then(|id| inputs.get(id).ok());
+    let composing = focused.is_some_and(|(_, input)| input.composing);
+    let composition_ended = focused.is_some_and(|(_, input)| input.composition_ended);
+    let selected = focused.is_some_and(|(terminal, _)| terminal.writer.selection.is_some());
+    for press in presses.read() {
+        if press.state == ButtonState::Released {
+            held.pressed.remove(&press.key_code);
+            if held.repeating.as_ref().is_some_and(|r| r.key_code == press.key_code) {
+                held.repeating = None;
+            }
+            continue;
+        }
+        let os_repeat = !held.pressed.insert(press.key_code);
+        // The input method takes the keys while it composes text.
+        if composing {
+            continue;
+        }
+        if is_cycle_key(&press.logical_key, mods)
+            || is_clipboard_key(&press.logical_key, mods, selected)
+        {
+            continue;
+        }
+        let Some(key) = to_inquire_key(&press.logical_key, mods) else {
+            continue;
+        };
+        // The key that confirmed or canceled a composition was the input
+        // method's, even though composing already stopped.
+        if composition_ended && matches!(key, Key::Enter | Key::Escape) {
+            continue;
+        }
+        let key = match key {
+            // Control chords don't produce text.
+            Key::Char(_, mods) if mods.contains(KeyModifiers::CONTROL) => key,
+            Key::Char(c, mods) => Key::Char(received.pop_front().unwrap_or(c), mods),
+            key => key,
+        };
+        if os_repeat {
+            continue;
+        }
+        keys.push(key);
+        held.repeating = Some(Repeating {
+            key_code: press.key_code,
+            key,
+            wait: repeat.delay,
+        });
+        pressed_now = true;
+    }
+    if !composing {
+        keys.extend(received.into_iter().map(|c| Key::Char(c, mods)));
+    }
+    if let Some(repeating) = held.repeating.as_mut().filter(|_| !pressed_now) {
+        repeat.tick(repeating.key, &mut repeating.wait, time.delta(), &mut keys);
+    }
+    if let Some((_, mut input)) = focus.0.and_then(|id| inputs.get_mut(id).ok()) {
+        if input.composition_ended {
+            input.composition_ended = false;
+        }
+        if !keys.is_empty() {
+            input.keys.extend(keys);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_logical_keys() {
+        let none = KeyModifiers::NONE;
+        let ctrl = KeyModifiers::CONTROL;
+        let a = LogicalKey::Character("a".into());
+        assert_eq!(to_inquire_key(&a, none), Some(Key::Char('a', none)));
+        assert_eq!(to_inquire_key(&a, ctrl), Some(Key::Char('a', ctrl)));
+        assert_eq!(to_inquire_key(&LogicalKey::Space, none), Some(Key::Char(' ', none)));
+        assert_eq!(to_inquire_key(&LogicalKey::Enter, ctrl), Some(Key::Enter));
+        assert_eq!(to_inquire_key(&LogicalKey::Tab, KeyModifiers::SHIFT), Some(Key::Tab));
+        assert_eq!(to_inquire_key(&LogicalKey::ArrowLeft, ctrl), Some(Key::Left(ctrl)));
+        assert_eq!(to_inquire_key(&LogicalKey::PageDown, none), Some(Key::PageDown(none)));
+        assert_eq!(to_inquire_key(&LogicalKey::F1, none), None);
+        assert_eq!(to_inquire_key(&LogicalKey::Character("".into()), none), None);
+    }
+
+    #[test]
+    fn repeats_after_the_delay_at_the_interval() {
+        let repeat = KeyRepeat::default();
+        let key = Key::Backspace;
+        let mut wait = repeat.delay;
+        let mut keys = vec![];
+        repeat.tick(key, &mut wait, Duration::from_millis(400), &mut keys);
+        assert!(keys.is_empty());
+        repeat.tick(key, &mut wait, Duration::from_millis(100), &mut keys);
+        assert_eq!(keys.len(), 1);
+        assert_eq!(wait, repeat.interval);
+        repeat.tick(key, &mut wait, Duration::from_millis(70), &mut keys);
+        assert_eq!(keys.len(), 3);
+        assert_eq!(wait, Duration::from_millis(29));
+    }
+
+    #[test]
+    fn long_frames_repeat_a_few_times_only() {
+        let repeat = KeyRepeat::default();
+        let mut wait = repeat.delay;
+        let mut keys = vec![];
+        repeat.tick(Key::Backspace, &mut wait, Duration::from_secs(5), &mut keys);
+        assert_eq!(keys.len(), MAX_REPEATS_PER_TICK);
+        assert_eq!(wait, repeat.interval);
+    }
+
+    #[test]
+    fn disabled_repeat_does_nothing() {
+        let repeat = KeyRepeat {
+            enabled: false,
+            ..default()
+        };
+        let mut wait = Duration::ZERO;
+        let mut keys = vec![];
+        repeat.tick(Key::Enter, &mut wait, Duration::from_secs(1), &mut keys);
+        assert!(keys.is_empty());
+    }
+}
@@ -1,32 +1,99 @@
+// Bevy systems take queries whose types clippy considers complex.
+#![allow(clippy::type_complexity)]
+
+use std::collections::VecDeque;
 use std::fmt::Display;
 use std::io::Result;
 use std::io::Write;
 use inquire::error::InquireResult;
-use inquire::InquireError;
 use inquire::terminal::{Terminal, TerminalSize};
 use inquire::ui::{Key, Styled, KeyModifiers, InputReader};
 mod text_style_adapter;
+mod attributes;
+mod palette;
+mod plugin;
+mod driver;
+mod events;
+mod commands;
+mod ask;
+mod input;
+mod focus;
+mod mouse;
+mod touch;
+mod gamepad;
+mod clipboard;
+mod ime;
+mod size;
+mod grid;
 
 use text_style_adapter::StyledStringWriter;
+use driver::WorkerLink;
+pub use text_style_adapter::{BevySettings, RenderMode};
+pub use attributes::TextAttributes;
+pub use palette::{TerminalPalette, TerminalPaletteLoader, TerminalPaletteLoaderError};
+pub use plugin::{InquirePlugin, InquireSet};
+pub use driver::{BevyPrompt, InputExhausted, OptionPrefixes, PromptRunner};
+pub use events::{InquireAppExt, PromptCanceled, PromptCompleted};
+pub use commands::InquireCommandsExt;
+pub use ask::Inquirer;
+pub use input::{to_inquire_key, KeyRepeat};
+pub use focus::{TerminalFocus, TerminalFocusChanged};
+pub use mouse::MouseSettings;
+pub use touch::{OnScreenKeyboard, TouchSettings};
+pub use gamepad::GamepadBindings;
+pub use clipboard::{Clipboard, ClipboardProvider, MemoryClipboard};
+pub use ime::ImeSettings;
+pub use size::TerminalResized;
+pub use grid::{Cell, CellBuffer, TerminalTarget};
+#[cfg(feature = "system-clipboard")]
+pub use clipboard::SystemClipboard;
 use bevy::prelude::*;
 
 #[derive(Component)]
 pub struct BevyTerminal {
     size: TerminalSize,
     writer: StyledStringWriter,
+    /// Set on the terminal a prompt draws on from its worker thread.
+    link: Option<WorkerLink>,
 }
 
 impl Default for BevyTerminal {
     fn default() -> Self {
+        Self::with_size(TerminalSize::new(80, 24))
+    }
+}
+
+impl BevyTerminal {
+    /// A terminal of `size` columns and lines. Terminals whose UI node has a
+    /// set width or height are refitted to it.
+    pub fn with_size(size: TerminalSize) -> Self {
         Self {
-            size: TerminalSize::new(24, 80),// { width: 80, height: 24 },
-            writer: StyledStringWriter::default()
+            size,
+            writer: StyledStringWriter::default(),
+            link: None,
         }
     }
+
+    pub fn size(&self) -> TerminalSize {
+        self.size
+    }
+
+    /// The last drawn frame laid out on a grid of this terminal's size, with
+    /// colors from `palette`.
+    pub fn cells(&self, palette: &TerminalPalette) -> CellBuffer {
+        CellBuffer::from_lines(self.size, self.writer.styled_lines(), palette)
+    }
 }
 
+/// Keys received by a terminal, oldest first.
+#[derive(Component, Default)]
 pub struct BevyInput {
-    keys: Vec<Key>,
+    pub(crate) keys: VecDeque<Key>,
+    /// Set while an input method is composing text, which takes the keys.
+    pub(crate) composing: bool,
+    /// Set on the frame a composition ends, whose Enter or Escape press
+    /// confirmed or canceled it rather than the prompt.
+    pub(crate) composition_ended: bool,
 }
 
 pub fn from_input(input: &ButtonInput<KeyCode>) -> KeyModifiers {
@@ -48,13 +115,13 @@ pub fn from_input(input: &ButtonInput<KeyCode>) -> KeyModifiers {
 
 impl InputReader for BevyInput {
     fn read_key(&mut self) -> InquireResult<Key> {
-        self.keys.pop().ok_or(InquireError::OperationCanceled)
+        self.keys.pop_front().ok_or_else(driver::input_exhausted)
     }
 }
 
 impl Terminal for BevyTerminal {
     fn get_size(&self) -> Result<TerminalSize> {
-        Ok(self.size)
+        Ok(self.link.as_ref().map_or(self.size, WorkerLink::size))
     }
 
     fn write<T: Display>(&mut self, val: T) -> Result<()> {
@@ -62,11 +129,21 @@ impl Terminal for BevyTerminal {
     }
 
     fn write_styled<T: Display>(&mut self, val: &Styled<T>) -> Result<()> {
-        write!(self.writer, "{}", val.content)
+        let style = std::mem::replace(&mut self.writer.style, val.style);
+        let result = write!(self.writer, "{}", val.content);
+        self.writer.style = style;
+        result
     }
 
-    fn clear_line(&mut self) -> Result<()> { todo!() }
-    fn clear_until_new_line(&mut self) -> Result<()> { todo!() }
+    fn clear_line(&mut self) -> Result<()> {
+        self.writer.clear_line();
+        Ok(())
+    }
+
+    fn clear_until_new_line(&mut self) -> Result<()> {
+        self.writer.clear_until_new_line();
+        Ok(())
+    }
 
     fn cursor_hide(&mut self) -> Result<()> {
         self.writer.state.cursor_visible = false;
@@ -77,11 +154,91 @@ impl Terminal for BevyTerminal {
         self.writer.state.cursor_visible = true;
         Ok(())
     }
-    fn cursor_up(&mut self, cnt: u16) -> Result<()> { todo!() }
-    fn cursor_down(&mut self, cnt: u16) -> Result<()> { todo!() }
-    fn cursor_left(&mut self, cnt: u16) -> Result<()> { todo!() }
-    fn cursor_right(&mut self, cnt: u16) -> Result<()> { todo!() }
-    fn cursor_move_to_column(&mut self, idx: u16) -> Result<()> { todo!() }
 
-    fn flush(&mut self) -> Result<()> { todo!() }
+    fn cursor_up(&mut self, cnt: u16) -> Result<()> {
+        self.writer.cursor_up(cnt as usize);
+        Ok(())
+    }
+
+    fn cursor_down(&mut self, cnt: u16) -> Result<()> {
+        self.writer.cursor_down(cnt as usize);
+        Ok(())
+    }
+
+    fn cursor_left(&mut self, cnt: u16) -> Result<()> {
+        self.writer.cursor_left(cnt as usize);
+        Ok(())
+    }
+
+    fn cursor_right(&mut self, cnt: u16) -> Result<()> {
+        self.writer.cursor_right(cnt as usize);
+        Ok(())
+    }
+
+    fn cursor_move_to_column(&mut self, idx: u16) -> Result<()> {
+        self.writer.cursor_to_column(idx as usize);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.commit();
+        if let Some(link) = &self.link {
+            link.send_frame(self.writer.last_frame());
+        }
+        Ok(())
+    }
+}
+
+impl Terminal for &mut BevyTerminal {
+    fn get_size(&self) -> Result<TerminalSize> {
+        Terminal::get_size(&**self)
+    }
+
+    fn write<T: Display>(&mut self, val: T) -> Result<()> {
+        Terminal::write(&mut **self, val)
+    }
+
+    fn write_styled<T: Display>(&mut self, val: &Styled<T>) -> Result<()> {
+        Terminal::write_styled(&mut **self, val)
+    }
+
+    fn clear_line(&mut self) -> Result<()> {
+        Terminal::clear_line(&mut **self)
+    }
+
+    fn clear_until_new_line(&mut self) -> Result<()> {
+        Terminal::clear_until_new_line(&mut **self)
+    }
+
+    fn cursor_hide(&mut self) -> Result<()> {
+        Terminal::cursor_hide(&mut **self)
+    }
+
+    fn cursor_show(&mut self) -> Result<()> {
+        Terminal::cursor_show(&mut **self)
+    }
+
+    fn cursor_up(&mut self, cnt: u16) -> Result<()> {
+        Terminal::cursor_up(&mut **self, cnt)
+    }
+
+    fn cursor_down(&mut self, cnt: u16) -> Result<()> {
+        Terminal::cursor_down(&mut **self, cnt)
+    }
+
+    fn cursor_left(&mut self, cnt: u16) -> Result<()> {
+        Terminal::cursor_left(&mut **self, cnt)
+    }
+
+    fn cursor_right(&mut self, cnt: u16) -> Result<()> {
+        Terminal::cursor_right(&mut **self, cnt)
+    }
+
+    fn cursor_move_to_column(&mut self, idx: u16) -> Result<()> {
+        Terminal::cursor_move_to_column(&mut **self, idx)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Terminal::flush(&mut **self)
+    }
 }
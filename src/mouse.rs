@@ -0,0 +1,324 @@
+use std::time::Duration;
+
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::input::touch::Touch;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use inquire::ui::{Key, KeyModifiers};
+
+use crate::driver::{OptionPrefixes, PromptRunner};
+use crate::text_style_adapter::CursorPos;
+use crate::touch::TouchSettings;
+use crate::{BevyInput, BevyTerminal};
+
+/// Pixels of scrolling, from touchpads and the like, that make up a notch of
+/// a mouse wheel.
+const PIXELS_PER_NOTCH: f32 = 50.0;
+
+/// Mouse input on terminals, turned into the keys that do the same thing.
+///
+/// Clicking an option of a list prompt highlights it and double-clicking
+/// submits it. Clicking the line being edited moves the cursor there. Each
+/// notch of the mouse wheel scrolls a page. Dragging selects text, which
+/// Ctrl+C copies, see [Clipboard](crate::Clipboard).
+#[derive(Resource, Debug, Clone)]
+pub struct MouseSettings {
+    pub enabled: bool,
+    /// Longest time between the two clicks of a double click.
+    pub double_click: Duration,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            double_click: Duration::from_millis(400),
+        }
+    }
+}
+
+/// A rendered line of a terminal.
+#[derive(Component, Debug, Clone, Copy)]
+pub(crate) struct TerminalLine {
+    pub(crate) terminal: Entity,
+    pub(crate) line: usize,
+}
+
+/// A rendered run of characters on a [TerminalLine].
+#[derive(Component, Debug, Clone, Copy)]
+pub(crate) struct TerminalSpan {
+    pub(crate) column: usize,
+    pub(crate) len: usize,
+}
+
+pub(crate) struct Click {
+    terminal: Entity,
+    line: usize,
+    at: Duration,
+}
+
+/// The keys of a tap, held back until the finger is lifted in case the touch
+/// turns into a swipe.
+pub(crate) struct PendingTap {
+    touch: u64,
+    terminal: Entity,
+    keys: Vec<Key>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn click_to_keys(
+    settings: Res<MouseSettings>,
+    touch_settings: Res<TouchSettings>,
+    time: Res<Time<Real>>,
+    touches: Res<Touches>,
+    mut last_click: Local<Option<Click>>,
+    mut pending: Local<Option<PendingTap>>,
+    lines: Query<(&TerminalLine, &Interaction, &Children), Changed<Interaction>>,
+    spans: Query<(&TerminalSpan, &RelativeCursorPosition)>,
+    mut terminals: Query<(&BevyTerminal, &PromptRunner, &mut BevyInput)>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    // Touches press UI nodes too; swipes are taken by `swipe_to_keys`.
+    let tap = touch_settings
+        .enabled
+        .then(|| touches.iter_just_pressed().next().map(Touch::id))
+        .flatten();
+    for (line, interaction, children) in &lines {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Ok((terminal, runner, mut input)) = terminals.get_mut(line.terminal) else {
+            continue;
+        };
+        // Clicks past the end of the line go to its end.
+        let column = spans
+            .iter_many(children)
+            .find(|(_, position)| position.mouse_over())
+            .map(|(span, position)| span_column(span, position))
+            .or_else(|| spans.iter_many(children).map(|(span, _)| span.column + span.len).max())
+            .unwrap_or(0);
+        let now = time.elapsed();
+        let double = last_click.as_ref().is_some_and(|click| {
+            click.terminal == line.terminal
+                && click.line == line.line
+                && now - click.at <= settings.double_click
+        });
+        *last_click = (!double).then_some(Click {
+            terminal: line.terminal,
+            line: line.line,
+            at: now,
+        });
+        let keys = match &runner.option_prefixes {
+            Some(prefixes) => option_keys(terminal, prefixes, line.line, double),
+            None => None,
+        };
+        let keys = keys.unwrap_or_else(|| cursor_keys(terminal, line.line, column));
+        match tap {
+            Some(touch) => {
+                *pending = Some(PendingTap {
+                    touch,
+                    terminal: line.terminal,
+                    keys,
+                })
+            }
+            None => input.keys.extend(keys),
+        }
+    }
+
+    let Some(tap) = pending.take() else {
+        return;
+    };
+    if let Some(touch) = touches.get_released(tap.touch) {
+        if touch.distance().length() < touch_settings.swipe_distance {
+            if let Ok((_, _, mut input)) = terminals.get_mut(tap.terminal) {
+                input.keys.extend(tap.keys);
+            }
+        }
+    } else if touches.get_pressed(tap.touch).is_some() {
+        *pending = Some(tap);
+    }
+}
+
+/// The column under the mouse on `span`.
+fn span_column(span: &TerminalSpan, position: &RelativeCursorPosition) -> usize {
+    let x = position.normalized.map_or(0.0, |p| p.x);
+    span.column + ((x * span.len as f32) as usize).min(span.len.saturating_sub(1))
+}
+
+/// Selects text by dragging the mouse over a terminal. A click without a
+/// drag clears the selection.
+pub(crate) fn select_text(
+    settings: Res<MouseSettings>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut anchor: Local<Option<(Entity, CursorPos)>>,
+    spans: Query<(&TerminalSpan, &RelativeCursorPosition, &Parent)>,
+    lines: Query<&TerminalLine>,
+    mut terminals: Query<&mut BevyTerminal>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    let hovered = spans
+        .iter()
+        .find(|(_, position, _)| position.mouse_over())
+        .and_then(|(span, position, parent)| {
+            let line = lines.get(parent.get()).ok()?;
+            let pos = CursorPos {
+                line: line.line,
+                column: span_column(span, position),
+            };
+            Some((line.terminal, pos))
+        });
+    if buttons.just_pressed(MouseButton::Left) {
+        *anchor = hovered;
+        for mut terminal in &mut terminals {
+            if terminal.writer.selection.is_some() {
+                terminal.writer.selection = None;
+                terminal.writer.dirty = true;
+            }
+        }
+    } else if buttons.pressed(MouseButton::Left) {
+        let (Some((id, from)), Some((hovered_id, to))) = (*anchor, hovered) else {
+            return;
+        };
+        let Ok(mut terminal) = terminals.get_mut(id) else {
+            return;
+        };
+        if id != hovered_id {
+            return;
+        }
+        let (start, mut end) = (from.min(to), from.max(to));
+        // The character under the mouse is selected too.
+        end.column += 1;
+        let selection = (from != to).then_some((start, end));
+        if terminal.writer.selection != selection {
+            terminal.writer.selection = selection;
+            terminal.writer.dirty = true;
+        }
+    } else {
+        *anchor = None;
+    }
+}
+
+/// Keys that move the highlight to the option on `line`, and submit it on a
+/// double click. `None` if `line` is not an option.
+fn option_keys(
+    terminal: &BevyTerminal,
+    prefixes: &OptionPrefixes,
+    line: usize,
+    double: bool,
+) -> Option<Vec<Key>> {
+    let lines = terminal.writer.frame_lines();
+    let is_option = |i: &usize| {
+        let text = &lines[*i];
+        text.starts_with(' ')
+            || [&prefixes.highlighted, &prefixes.scroll_up, &prefixes.scroll_down]
+                .iter()
+                .any(|prefix| !prefix.is_empty() && text.starts_with(prefix.as_str()))
+    };
+    // The first line is the prompt itself.
+    let highlighted = (1..lines.len()).find(|&i| {
+        !prefixes.highlighted.is_empty() && lines[i].starts_with(prefixes.highlighted.as_str())
+    })?;
+    let first = (1..=highlighted).rev().take_while(is_option).last()?;
+    let last = (highlighted..lines.len()).take_while(is_option).last()?;
+    if !(first..=last).contains(&line) {
+        return None;
+    }
+    let mut keys = if line < highlighted {
+        vec![Key::Up(KeyModifiers::NONE); highlighted - line]
+    } else {
+        vec![Key::Down(KeyModifiers::NONE); line - highlighted]
+    };
+    if double {
+        keys.push(Key::Enter);
+    }
+    Some(keys)
+}
+
+/// Keys that move the cursor to `column` if it is on `line`.
+fn cursor_keys(terminal: &BevyTerminal, line: usize, column: usize) -> Vec<Key> {
+    match terminal.writer.frame_cursor {
+        Some(cursor) if cursor.line == line && column < cursor.column => {
+            vec![Key::Left(KeyModifiers::NONE); cursor.column - column]
+        }
+        Some(cursor) if cursor.line == line => {
+            vec![Key::Right(KeyModifiers::NONE); column - cursor.column]
+        }
+        _ => vec![],
+    }
+}
+
+pub(crate) fn wheel_to_keys(
+    settings: Res<MouseSettings>,
+    mut wheel: EventReader<MouseWheel>,
+    mut pixels: Local<f32>,
+    mut terminals: Query<(&Interaction, &mut BevyInput), With<BevyTerminal>>,
+) {
+    let mut notches = 0;
+    for event in wheel.read() {
+        match event.unit {
+            MouseScrollUnit::Line => notches += event.y.round() as i32,
+            MouseScrollUnit::Pixel => {
+                *pixels += event.y;
+                let whole = (*pixels / PIXELS_PER_NOTCH).trunc();
+                *pixels -= whole * PIXELS_PER_NOTCH;
+                notches += whole as i32;
+            }
+        }
+    }
+    if notches == 0 || !settings.enabled {
+        return;
+    }
+    let key = if notches > 0 {
+        Key::PageUp(KeyModifiers::NONE)
+    } else {
+        Key::PageDown(KeyModifiers::NONE)
+    };
+    for (interaction, mut input) in &mut terminals {
+        if *interaction != Interaction::None {
+            input.keys.extend(vec![key; notches.unsigned_abs() as usize]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn select(text: &str) -> BevyTerminal {
+        let mut terminal = BevyTerminal::default();
+        write!(terminal.writer, "{text}").unwrap();
+        terminal.writer.commit();
+        terminal
+    }
+
+    #[test]
+    fn clicks_move_the_highlight_to_an_option() {
+        let terminal = select("? Pick one\n  a\n> b\n  c\nv d\n[up and down to move]");
+        let prefixes = OptionPrefixes {
+            highlighted: "> ".to_string(),
+            scroll_up: "^ ".to_string(),
+            scroll_down: "v ".to_string(),
+        };
+        let none = KeyModifiers::NONE;
+        assert_eq!(option_keys(&terminal, &prefixes, 1, false), Some(vec![Key::Up(none)]));
+        assert_eq!(option_keys(&terminal, &prefixes, 2, false), Some(vec![]));
+        assert_eq!(
+            option_keys(&terminal, &prefixes, 4, false),
+            Some(vec![Key::Down(none), Key::Down(none)])
+        );
+        assert_eq!(
+            option_keys(&terminal, &prefixes, 3, true),
+            Some(vec![Key::Down(none), Key::Enter])
+        );
+        assert_eq!(option_keys(&terminal, &prefixes, 2, true), Some(vec![Key::Enter]));
+        // The prompt and help lines are not options.
+        assert_eq!(option_keys(&terminal, &prefixes, 0, false), None);
+        assert_eq!(option_keys(&terminal, &prefixes, 5, true), None);
+    }
+}
@@ -0,0 +1,291 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use bevy::asset::io::Reader;
+use bevy::asset::{ron, AssetLoader, AsyncReadExt, BoxedFuture, LoadContext};
+use bevy::prelude::*;
+use bevy::render::color::HexColorError;
+use serde::Deserialize;
+
+use crate::BevyTerminal;
+
+/// Colors a terminal is drawn with.
+///
+/// The 16 named inquire [Color](inquire::ui::Color)s are looked up in `ansi`,
+/// in ANSI order: black, red, green, yellow, blue, magenta, cyan and white,
+/// then their light variants. `Color::AnsiValue(n)` uses `ansi` for the first
+/// 16 values and the standard xterm 6x6x6 color cube and 24-step greyscale for
+/// the rest. Unstyled text uses `foreground` on `background`.
+///
+/// The palette resource is used by every terminal that has no
+/// `Handle<TerminalPalette>` component. Palettes can also be loaded from
+/// `.palette.ron` or `.palette.json` files of hex colors:
+///
+/// ```ron
+/// (
+///     ansi: ["#000000", "#aa0000", "#00aa00", "#aa5500", "#0000aa", "#aa00aa", "#00aaaa", "#aaaaaa",
+///            "#555555", "#ff5555", "#55ff55", "#ffff55", "#5555ff", "#ff55ff", "#55ffff", "#ffffff"],
+///     foreground: "#aaaaaa",
+///     background: "#000000",
+///     cursor: "#aaaaaa",
+/// )
+/// ```
+#[derive(Asset, TypePath, Resource, Debug, Clone, PartialEq)]
+pub struct TerminalPalette {
+    pub ansi: [Color; 16],
+    pub foreground: Color,
+    pub background: Color,
+    pub cursor: Color,
+}
+
+impl Default for TerminalPalette {
+    fn default() -> Self {
+        Self::vga()
+    }
+}
+
+impl TerminalPalette {
+    /// The classic VGA text mode colors.
+    pub fn vga() -> Self {
+        Self {
+            ansi: [
+                Color::rgb_u8(0, 0, 0),
+                Color::rgb_u8(170, 0, 0),
+                Color::rgb_u8(0, 170, 0),
+                Color::rgb_u8(170, 85, 0),
+                Color::rgb_u8(0, 0, 170),
+                Color::rgb_u8(170, 0, 170),
+                Color::rgb_u8(0, 170, 170),
+                Color::rgb_u8(170, 170, 170),
+                Color::rgb_u8(85, 85, 85),
+                Color::rgb_u8(255, 85, 85),
+                Color::rgb_u8(85, 255, 85),
+                Color::rgb_u8(255, 255, 85),
+                Color::rgb_u8(85, 85, 255),
+                Color::rgb_u8(255, 85, 255),
+                Color::rgb_u8(85, 255, 255),
+                Color::rgb_u8(255, 255, 255),
+            ],
+            foreground: Color::rgb_u8(170, 170, 170),
+            background: Color::rgb_u8(0, 0, 0),
+            cursor: Color::rgb_u8(170, 170, 170),
+        }
+    }
+
+    /// Ethan Schoonover's Solarized, dark variant.
+    pub fn solarized_dark() -> Self {
+        Self {
+            ansi: [
+                Color::rgb_u8(0x07, 0x36, 0x42),
+                Color::rgb_u8(0xdc, 0x32, 0x2f),
+                Color::rgb_u8(0x85, 0x99, 0x00),
+                Color::rgb_u8(0xb5, 0x89, 0x00),
+                Color::rgb_u8(0x26, 0x8b, 0xd2),
+                Color::rgb_u8(0xd3, 0x36, 0x82),
+                Color::rgb_u8(0x2a, 0xa1, 0x98),
+                Color::rgb_u8(0xee, 0xe8, 0xd5),
+                Color::rgb_u8(0x00, 0x2b, 0x36),
+                Color::rgb_u8(0xcb, 0x4b, 0x16),
+                Color::rgb_u8(0x58, 0x6e, 0x75),
+                Color::rgb_u8(0x65, 0x7b, 0x83),
+                Color::rgb_u8(0x83, 0x94, 0x96),
+                Color::rgb_u8(0x6c, 0x71, 0xc4),
+                Color::rgb_u8(0x93, 0xa1, 0xa1),
+                Color::rgb_u8(0xfd, 0xf6, 0xe3),
+            ],
+            foreground: Color::rgb_u8(0x83, 0x94, 0x96),
+            background: Color::rgb_u8(0x00, 0x2b, 0x36),
+            cursor: Color::rgb_u8(0x93, 0xa1, 0xa1),
+        }
+    }
+
+    /// The Dracula theme.
+    pub fn dracula() -> Self {
+        Self {
+            ansi: [
+                Color::rgb_u8(0x21, 0x22, 0x2c),
+                Color::rgb_u8(0xff, 0x55, 0x55),
+                Color::rgb_u8(0x50, 0xfa, 0x7b),
+                Color::rgb_u8(0xf1, 0xfa, 0x8c),
+                Color::rgb_u8(0xbd, 0x93, 0xf9),
+                Color::rgb_u8(0xff, 0x79, 0xc6),
+                Color::rgb_u8(0x8b, 0xe9, 0xfd),
+                Color::rgb_u8(0xf8, 0xf8, 0xf2),
+                Color::rgb_u8(0x62, 0x72, 0xa4),
+                Color::rgb_u8(0xff, 0x6e, 0x6e),
+                Color::rgb_u8(0x69, 0xff, 0x94),
+                Color::rgb_u8(0xff, 0xff, 0xa5),
+                Color::rgb_u8(0xd6, 0xac, 0xff),
+                Color::rgb_u8(0xff, 0x92, 0xdf),
+                Color::rgb_u8(0xa4, 0xff, 0xff),
+                Color::rgb_u8(0xff, 0xff, 0xff),
+            ],
+            foreground: Color::rgb_u8(0xf8, 0xf8, 0xf2),
+            background: Color::rgb_u8(0x28, 0x2a, 0x36),
+            cursor: Color::rgb_u8(0xf8, 0xf8, 0xf2),
+        }
+    }
+
+    /// Color for the xterm 256-color palette entry `n`.
+    pub fn ansi_value(&self, n: u8) -> Color {
+        const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+        match n {
+            0..=15 => self.ansi[n as usize],
+            16..=231 => {
+                let i = n - 16;
+                Color::rgb_u8(
+                    CUBE_LEVELS[(i / 36) as usize],
+                    CUBE_LEVELS[(i / 6 % 6) as usize],
+                    CUBE_LEVELS[(i % 6) as usize],
+                )
+            }
+            232..=255 => {
+                let grey = 8 + 10 * (n - 232);
+                Color::rgb_u8(grey, grey, grey)
+            }
+        }
+    }
+}
+
+/// The on-disk form of a [TerminalPalette].
+#[derive(Deserialize)]
+struct PaletteFile {
+    ansi: [String; 16],
+    foreground: String,
+    background: String,
+    cursor: String,
+}
+
+impl TryFrom<PaletteFile> for TerminalPalette {
+    type Error = HexColorError;
+
+    fn try_from(file: PaletteFile) -> Result<Self, HexColorError> {
+        let mut ansi = [Color::BLACK; 16];
+        for (color, hex) in ansi.iter_mut().zip(&file.ansi) {
+            *color = Color::hex(hex)?;
+        }
+        Ok(Self {
+            ansi,
+            foreground: Color::hex(&file.foreground)?,
+            background: Color::hex(&file.background)?,
+            cursor: Color::hex(&file.cursor)?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum TerminalPaletteLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    Json(serde_json::Error),
+    Color(HexColorError),
+}
+
+impl Display for TerminalPaletteLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not read palette: {}", e),
+            Self::Ron(e) => write!(f, "could not parse palette: {}", e),
+            Self::Json(e) => write!(f, "could not parse palette: {}", e),
+            Self::Color(e) => write!(f, "invalid palette color: {}", e),
+        }
+    }
+}
+
+impl Error for TerminalPaletteLoaderError {}
+
+impl From<std::io::Error> for TerminalPaletteLoaderError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for TerminalPaletteLoaderError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        Self::Ron(e)
+    }
+}
+
+impl From<serde_json::Error> for TerminalPaletteLoaderError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+impl From<HexColorError> for TerminalPaletteLoaderError {
+    fn from(e: HexColorError) -> Self {
+        Self::Color(e)
+    }
+}
+
+#[derive(Default)]
+pub struct TerminalPaletteLoader;
+
+impl AssetLoader for TerminalPaletteLoader {
+    type Asset = TerminalPalette;
+    type Settings = ();
+    type Error = TerminalPaletteLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<TerminalPalette, TerminalPaletteLoaderError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let file: PaletteFile = match load_context.path().extension() {
+                Some(ext) if ext == "json" => serde_json::from_slice(&bytes)?,
+                _ => ron::de::from_bytes(&bytes)?,
+            };
+            Ok(TerminalPalette::try_from(file)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["palette.ron", "palette.json"]
+    }
+}
+
+/// Redraws terminals whose palette was changed, loaded or hot-reloaded.
+pub(crate) fn redraw_on_palette_change(
+    mut events: EventReader<AssetEvent<TerminalPalette>>,
+    default_palette: Res<TerminalPalette>,
+    mut terminals: Query<(&mut BevyTerminal, Option<Ref<Handle<TerminalPalette>>>)>,
+) {
+    let changed: Vec<AssetId<TerminalPalette>> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } | AssetEvent::LoadedWithDependencies { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    for (mut terminal, handle) in &mut terminals {
+        let redraw = match handle {
+            Some(handle) => handle.is_changed() || changed.contains(&handle.id()),
+            None => default_palette.is_changed(),
+        };
+        if redraw {
+            terminal.writer.dirty = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ansi_value_uses_the_palette_then_the_cube_and_greys() {
+        let palette = TerminalPalette::vga();
+        assert_eq!(palette.ansi_value(1), palette.ansi[1]);
+        assert_eq!(palette.ansi_value(15), palette.ansi[15]);
+        assert_eq!(palette.ansi_value(16), Color::rgb_u8(0, 0, 0));
+        assert_eq!(palette.ansi_value(196), Color::rgb_u8(255, 0, 0));
+        assert_eq!(palette.ansi_value(110), Color::rgb_u8(135, 175, 215));
+        assert_eq!(palette.ansi_value(231), Color::rgb_u8(255, 255, 255));
+        assert_eq!(palette.ansi_value(232), Color::rgb_u8(8, 8, 8));
+        assert_eq!(palette.ansi_value(255), Color::rgb_u8(238, 238, 238));
+    }
+}
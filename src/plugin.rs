@@ -0,0 +1,176 @@
+use bevy::ecs::schedule::{InternedScheduleLabel, ScheduleLabel};
+use bevy::prelude::*;
+use inquire::list_option::ListOption;
+
+use crate::ask::{prompt_channel, receive_prompt_requests};
+use crate::driver::drive_prompts;
+use crate::events::{InquireAppExt, PromptCanceled};
+use crate::clipboard::{copy_and_paste, Clipboard};
+use crate::grid::{render_grids, GlyphAtlas, TerminalGrid, TerminalTarget};
+use crate::gamepad::{gamepad_to_keys, GamepadBindings};
+use crate::focus::{
+    add_interaction, send_focus_changes, update_focus, TerminalFocus, TerminalFocusChanged,
+};
+use crate::ime::{enable_ime, ime_to_keys, place_ime, ImeSettings};
+use crate::input::{collect_keys, KeyRepeat};
+use crate::mouse::{click_to_keys, select_text, wheel_to_keys, MouseSettings};
+use crate::palette::{redraw_on_palette_change, TerminalPalette, TerminalPaletteLoader};
+use crate::size::{cell_size, fit_terminals, TerminalResized};
+use crate::text_style_adapter::{BevySettings, LineCache, RenderMode};
+use crate::touch::{
+    build_on_screen_keyboards, press_on_screen_keys, relabel_on_screen_keys, swipe_to_keys,
+    TouchSettings,
+};
+use crate::{BevyInput, BevyTerminal};
+
+/// System sets used by [InquirePlugin]. They run in the order they are
+/// declared, so other UI systems can be placed before or after them.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InquireSet {
+    /// Focus is updated, keyboard input is collected into the focused
+    /// terminal's [BevyInput] and mouse, touch, on-screen keyboard and
+    /// gamepad input into the terminal it's meant for.
+    Input,
+    /// Prompts consume their input and draw into their [BevyTerminal].
+    Prompt,
+    /// Terminals are drawn into the Bevy UI hierarchy, or wherever their
+    /// [TerminalTarget](crate::TerminalTarget) says.
+    Render,
+}
+
+pub struct InquirePlugin {
+    /// Schedule the [InquireSet]s are added to.
+    pub schedule: InternedScheduleLabel,
+}
+
+impl Default for InquirePlugin {
+    fn default() -> Self {
+        Self {
+            schedule: Update.intern(),
+        }
+    }
+}
+
+impl Plugin for InquirePlugin {
+    fn build(&self, app: &mut App) {
+        let (inquirer, requests) = prompt_channel();
+        app.init_resource::<BevySettings>()
+            .init_resource::<TerminalPalette>()
+            .init_resource::<KeyRepeat>()
+            .init_resource::<TerminalFocus>()
+            .init_resource::<MouseSettings>()
+            .init_resource::<TouchSettings>()
+            .init_resource::<GamepadBindings>()
+            .init_resource::<Clipboard>()
+            .init_resource::<ImeSettings>()
+            .init_resource::<GlyphAtlas>()
+            .init_asset::<TerminalPalette>()
+            .init_asset_loader::<TerminalPaletteLoader>()
+            .insert_resource(inquirer)
+            .insert_resource(requests)
+            .add_event::<PromptCanceled>()
+            .add_event::<TerminalFocusChanged>()
+            .add_event::<TerminalResized>()
+            .add_prompt_output::<String>()
+            .add_prompt_output::<bool>()
+            .add_prompt_output::<ListOption<String>>()
+            .add_prompt_output::<Vec<ListOption<String>>>()
+            .add_prompt_output::<ListOption<&'static str>>()
+            .add_prompt_output::<Vec<ListOption<&'static str>>>()
+            .configure_sets(
+                self.schedule,
+                (InquireSet::Input, InquireSet::Prompt, InquireSet::Render).chain(),
+            )
+            .add_systems(
+                self.schedule,
+                (
+                    (
+                        (add_input, add_interaction),
+                        update_focus,
+                        (send_focus_changes, enable_ime),
+                        ime_to_keys,
+                        collect_keys,
+                        (
+                            copy_and_paste,
+                            click_to_keys,
+                            select_text,
+                            wheel_to_keys,
+                            swipe_to_keys,
+                            press_on_screen_keys,
+                            gamepad_to_keys,
+                        ),
+                    )
+                        .chain()
+                        .in_set(InquireSet::Input),
+                    (build_on_screen_keyboards, relabel_on_screen_keys).in_set(InquireSet::Render),
+                    (receive_prompt_requests, fit_terminals, drive_prompts)
+                        .chain()
+                        .in_set(InquireSet::Prompt),
+                    (redraw_on_palette_change, render_terminals, render_grids, place_ime)
+                        .chain()
+                        .in_set(InquireSet::Render),
+                ),
+            );
+    }
+}
+
+fn add_input(
+    mut commands: Commands,
+    terminals: Query<Entity, (With<BevyTerminal>, Without<BevyInput>)>,
+) {
+    for id in &terminals {
+        commands.entity(id).insert(BevyInput::default());
+    }
+}
+
+fn render_terminals(
+    mut commands: Commands,
+    settings: Res<BevySettings>,
+    fonts: Res<Assets<Font>>,
+    mut font_events: EventReader<AssetEvent<Font>>,
+    default_palette: Res<TerminalPalette>,
+    palettes: Res<Assets<TerminalPalette>>,
+    mut terminals: Query<
+        (
+            Entity,
+            &mut BevyTerminal,
+            Option<&Handle<TerminalPalette>>,
+            Option<&mut LineCache>,
+        ),
+        Without<TerminalTarget>,
+    >,
+) {
+    let rebuild = settings.is_changed() || font_events.read().count() > 0;
+    if settings.mode == RenderMode::Grid {
+        return;
+    }
+    // Until the font is loaded, guess at a monospace font's proportions.
+    let advance = cell_size(&settings, &fonts)
+        .map_or(settings.style.font_size * 0.6, |cell| cell.x);
+    for (id, mut terminal, handle, cache) in &mut terminals {
+        if !terminal.writer.dirty && !rebuild {
+            continue;
+        }
+        let palette = handle
+            .and_then(|handle| palettes.get(handle))
+            .unwrap_or(&default_palette);
+        commands.entity(id).insert(BackgroundColor(palette.background));
+        match cache {
+            Some(mut cache) => {
+                if rebuild {
+                    cache.invalidate();
+                }
+                terminal
+                    .writer
+                    .render(&mut commands, &settings, palette, id, &mut cache, advance);
+            }
+            None => {
+                let mut cache = LineCache::default();
+                terminal
+                    .writer
+                    .render(&mut commands, &settings, palette, id, &mut cache, advance);
+                commands.entity(id).insert(cache).remove::<TerminalGrid>();
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use ab_glyph::{Font as _, ScaleFont};
+use bevy::prelude::*;
+use inquire::terminal::TerminalSize;
+
+use crate::text_style_adapter::BevySettings;
+use crate::BevyTerminal;
+
+/// Sent when a terminal is resized to fit its UI node.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TerminalResized {
+    pub terminal: Entity,
+    pub size: TerminalSize,
+}
+
+/// Width of a character cell and height of a line of `settings`' font.
+pub(crate) fn cell_size(settings: &BevySettings, fonts: &Assets<Font>) -> Option<Vec2> {
+    let font = fonts.get(&settings.style.font)?;
+    let scaled = font.font.as_scaled(settings.style.font_size);
+    let advance = scaled.h_advance(scaled.glyph_id('M'));
+    let line_height = scaled.height() + scaled.line_gap();
+    (advance > 0.0 && line_height > 0.0).then_some(Vec2::new(advance, line_height))
+}
+
+/// Sizes terminals to as many columns and lines as fit their UI node.
+///
+/// Only sides with a set size are fitted; a node with an `auto` width or
+/// height grows with its text, so that side keeps its size. Running prompts
+/// use the new size from their next frame on.
+pub(crate) fn fit_terminals(
+    settings: Res<BevySettings>,
+    fonts: Res<Assets<Font>>,
+    mut font_events: EventReader<AssetEvent<Font>>,
+    mut events: EventWriter<TerminalResized>,
+    mut terminals: Query<(Entity, Ref<Node>, &Style, &mut BevyTerminal)>,
+) {
+    let font_changed = font_events.read().count() > 0 || settings.is_changed();
+    let Some(cell) = cell_size(&settings, &fonts) else {
+        return;
+    };
+    for (id, node, style, mut terminal) in &mut terminals {
+        if !node.is_changed() && !font_changed {
+            continue;
+        }
+        let fit = (node.size() / cell).floor();
+        let size = TerminalSize::new(
+            match style.width {
+                Val::Auto => terminal.size.width(),
+                _ => fit.x.clamp(1.0, u16::MAX as f32) as u16,
+            },
+            match style.height {
+                Val::Auto => terminal.size.height(),
+                _ => fit.y.clamp(1.0, u16::MAX as f32) as u16,
+            },
+        );
+        if terminal.size == size {
+            continue;
+        }
+        terminal.size = size;
+        events.send(TerminalResized { terminal: id, size });
+    }
+}
@@ -3,159 +3,754 @@ use std::io;
 use itertools::Itertools;
 
 use inquire::ui::*;
-use bevy::ecs::{entity::Entity, system::{Commands, Resource}};
-use bevy::text::TextStyle;
-use bevy::ui::{Style, FlexDirection, node_bundles::{TextBundle, NodeBundle}};
+use bevy::ecs::{component::Component, entity::Entity, system::{Commands, EntityCommands, Resource}};
+use bevy::text::{TextSection, TextStyle};
+use bevy::ui::{Style, FlexDirection, Interaction, PositionType, RelativeCursorPosition, Val, node_bundles::{TextBundle, NodeBundle}};
 // use bevy::prelude::*;
-use bevy::prelude::{BuildChildren, Color as BevyColor};
+use bevy::prelude::{BuildChildren, Color as BevyColor, DespawnRecursiveExt, Font, Handle};
 use bevy::utils::default;
 use bevy::hierarchy::ChildBuilder;
 
+use crate::attributes::TextAttributes;
+use crate::mouse::{TerminalLine, TerminalSpan};
+use crate::palette::TerminalPalette;
+
 #[derive(Resource, Debug, Default)]
 pub struct BevySettings {
+    /// Font and size of the text. Its color is taken from the terminal's
+    /// [TerminalPalette].
     pub style: TextStyle,
+    /// Font for [Attributes::BOLD] text. Uses `style.font` if unset.
+    pub bold_font: Option<Handle<Font>>,
+    /// Font for [Attributes::ITALIC] text. Uses `style.font` if unset.
+    pub italic_font: Option<Handle<Font>>,
+    /// Font for text that is both bold and italic. Falls back to the bold,
+    /// then the italic font.
+    pub bold_italic_font: Option<Handle<Font>>,
+    pub mode: RenderMode,
+}
+
+/// How the lines of a terminal are drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    /// A text node per styled span, laid out next to each other.
+    #[default]
+    Spans,
+    /// A text node per line, with a section per styled span. Backgrounds,
+    /// underlines and strikethroughs are quads placed by column, so the font
+    /// should be monospace. Keeps kerning intact and uses fewer UI nodes.
+    Lines,
+    /// A fixed grid of cells drawn into a single image, with each glyph cut
+    /// to its cell. Keeps box drawing and tree-like text lined up whatever
+    /// the font, but mouse clicks and selection are not supported.
+    Grid,
+}
+
+impl BevySettings {
+    pub(crate) fn font(&self, attributes: TextAttributes) -> Handle<Font> {
+        let bold = attributes.contains(TextAttributes::BOLD);
+        let italic = attributes.contains(TextAttributes::ITALIC);
+        let font = match (bold, italic) {
+            (true, true) => self.bold_italic_font.as_ref()
+                .or(self.bold_font.as_ref())
+                .or(self.italic_font.as_ref()),
+            (true, false) => self.bold_font.as_ref(),
+            (false, true) => self.italic_font.as_ref(),
+            (false, false) => None,
+        };
+        font.unwrap_or(&self.style.font).clone()
+    }
+}
+
+/// Colors and attributes of a [Span]. Unlike a [StyleSheet], it has room for
+/// all [TextAttributes].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct SpanStyle {
+    pub(crate) fg: Option<Color>,
+    pub(crate) bg: Option<Color>,
+    pub(crate) attributes: TextAttributes,
+    /// Marks the character under the cursor.
+    pub(crate) cursor: bool,
+}
+
+impl From<StyleSheet> for SpanStyle {
+    fn from(style: StyleSheet) -> Self {
+        Self {
+            fg: style.fg,
+            bg: style.bg,
+            attributes: style.att.into(),
+            cursor: false,
+        }
+    }
+}
+
+/// Text written in a single style.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Span {
+    pub(crate) content: String,
+    pub(crate) style: SpanStyle,
+}
+
+impl Span {
+    pub(crate) fn new(content: String) -> Self {
+        Self { content, style: SpanStyle::default() }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct StyledStringWriter {
     pub style: StyleSheet,
-    pub strings: Vec<Styled<String>>,
+    pub(crate) strings: Vec<Span>,
     pub state: RendererState,
     pub(crate) cursor_pos: Option<CursorPos>,
-    pub(crate) cursor_pos_save: Option<CursorPos>,
+    /// Last flushed frame, with the cursor drawn in.
+    pub(crate) frame: Vec<Span>,
+    /// Cursor position in the last flushed frame, if it was visible.
+    pub(crate) frame_cursor: Option<CursorPos>,
+    /// Set when a frame is flushed and cleared once it's rendered.
+    pub(crate) dirty: bool,
+    /// Text selected in the last flushed frame, from the first position up
+    /// to, but not including, the second.
+    pub(crate) selection: Option<(CursorPos, CursorPos)>,
+    /// Text being composed with an input method, drawn at the cursor.
+    pub(crate) preedit: Option<String>,
 }
 
-#[derive(Debug, Clone, Default)]
+/// A frame committed by a prompt, with the cursor drawn in.
+#[derive(Debug, Clone)]
+pub(crate) struct Frame {
+    spans: Vec<Span>,
+    cursor: Option<CursorPos>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) struct CursorPos {
-    index: usize,
-    len: usize
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+/// Where a character written at a [CursorPos] goes.
+enum Slot {
+    /// Overwrites the character at this span index and byte offset.
+    Replace(usize, usize),
+    /// Goes before the newline at this span index and byte offset.
+    Insert(usize, usize),
+    /// Goes at the end of the text.
+    Append,
+}
+
+/// What is found at a [CursorPos], before any padding.
+enum Found {
+    Char(usize, usize),
+    /// The newline ending the line, and how many columns short the line is.
+    LineEnd(usize, usize, usize),
+    /// The end of the text, and how many lines and columns short it is.
+    End(usize, usize),
 }
 
 impl StyledStringWriter {
-    pub fn clear(&mut self) {
-        self.state = RendererState::default();
-        self.cursor_pos = None;
-        self.cursor_pos_save = None;
+    /// Position just past the last character written.
+    fn end_pos(&self) -> CursorPos {
+        let mut pos = CursorPos::default();
+        for c in self.strings.iter().flat_map(|s| s.content.chars()) {
+            if c == '\n' {
+                pos.line += 1;
+                pos.column = 0;
+            } else {
+                pos.column += 1;
+            }
+        }
+        pos
+    }
+
+    fn get_cursor_pos(&self) -> CursorPos {
+        self.cursor_pos.unwrap_or_else(|| self.end_pos())
+    }
+
+    fn set_cursor_pos(&mut self, cursor_pos: CursorPos) {
+        // No position means the cursor follows the end of the text.
+        self.cursor_pos = (cursor_pos != self.end_pos()).then_some(cursor_pos);
+    }
+
+    pub fn cursor_up(&mut self, n: usize) {
+        let mut pos = self.get_cursor_pos();
+        pos.line = pos.line.saturating_sub(n);
+        self.set_cursor_pos(pos);
+    }
+
+    pub fn cursor_down(&mut self, n: usize) {
+        let mut pos = self.get_cursor_pos();
+        pos.line += n;
+        self.set_cursor_pos(pos);
+    }
+
+    pub fn cursor_left(&mut self, n: usize) {
+        let mut pos = self.get_cursor_pos();
+        pos.column = pos.column.saturating_sub(n);
+        self.set_cursor_pos(pos);
+    }
+
+    pub fn cursor_right(&mut self, n: usize) {
+        let mut pos = self.get_cursor_pos();
+        pos.column += n;
+        self.set_cursor_pos(pos);
+    }
+
+    pub fn cursor_to_column(&mut self, column: usize) {
+        let mut pos = self.get_cursor_pos();
+        pos.column = column;
+        self.set_cursor_pos(pos);
+    }
+
+    /// Blanks the cursor's line. The cursor doesn't move.
+    pub fn clear_line(&mut self) {
+        self.truncate_line(0);
+    }
+
+    /// Blanks the cursor's line from the cursor to the end of the line. The
+    /// cursor doesn't move.
+    pub fn clear_until_new_line(&mut self) {
+        let column = self.get_cursor_pos().column;
+        self.truncate_line(column);
+    }
+
+    /// Removes the characters of the cursor's line from column `from` on,
+    /// keeping the newline that ends it.
+    fn truncate_line(&mut self, from: usize) {
+        let pos = self.get_cursor_pos();
+        let mut line = 0;
+        let mut column = 0;
+        for s in &mut self.strings {
+            if line > pos.line {
+                break;
+            }
+            let mut kept = String::with_capacity(s.content.len());
+            for c in s.content.chars() {
+                if c == '\n' {
+                    line += 1;
+                    column = 0;
+                } else {
+                    column += 1;
+                    if line == pos.line && column > from {
+                        continue;
+                    }
+                }
+                kept.push(c);
+            }
+            s.content = kept;
+        }
+        self.normalize();
+        self.set_cursor_pos(pos);
     }
 
-    fn get_cursor_pos(&mut self) -> CursorPos {
-        if self.strings.len() == 0 {
-            self.strings.push(Styled { content: String::new(), style: self.style });
+    fn find(&self, pos: CursorPos) -> Found {
+        let mut line = 0;
+        let mut column = 0;
+        for (i, s) in self.strings.iter().enumerate() {
+            for (b, c) in s.content.char_indices() {
+                if line == pos.line {
+                    if c == '\n' {
+                        return Found::LineEnd(i, b, pos.column - column);
+                    }
+                    if column == pos.column {
+                        return Found::Char(i, b);
+                    }
+                }
+                if c == '\n' {
+                    line += 1;
+                    column = 0;
+                } else {
+                    column += 1;
+                }
+            }
         }
-        match &self.cursor_pos {
-            None => CursorPos { index: self.strings.len() - 1, len: self.strings.last().unwrap().content.chars().count() },
-            Some(c) => c.clone()
+        if line < pos.line {
+            Found::End(pos.line - line, pos.column)
+        } else {
+            Found::End(0, pos.column - column)
         }
     }
 
-    fn set_cursor_pos(&mut self, cursor_pos: CursorPos) {
-        // if cursor_pos.len < 0 {
-        //     cursor_pos.index -= 1;
-        //     cursor_pos.len += self.strings[cursor_pos.index].s.chars().count();
-        // }
-        self.cursor_pos = Some(cursor_pos);
+    /// Finds where a character at `pos` goes, padding the text with blanks
+    /// if it doesn't reach that far.
+    fn slot(&mut self, pos: CursorPos) -> Slot {
+        match self.find(pos) {
+            Found::Char(i, b) => Slot::Replace(i, b),
+            Found::LineEnd(i, b, 0) => Slot::Insert(i, b),
+            Found::LineEnd(i, b, pad) => {
+                self.splice(i, b..b, Span::new(" ".repeat(pad)));
+                self.slot(pos)
+            }
+            Found::End(lines, columns) => {
+                let pad = "\n".repeat(lines) + &" ".repeat(columns);
+                self.append(&pad, SpanStyle::default());
+                Slot::Append
+            }
+        }
+    }
+
+    /// Replaces the bytes in `range` of span `i` with `styled`.
+    fn splice(&mut self, i: usize, range: std::ops::Range<usize>, styled: Span) {
+        let span = self.strings.remove(i);
+        let left = Span { content: span.content[..range.start].to_string(), style: span.style };
+        let right = Span { content: span.content[range.end..].to_string(), style: span.style };
+        let _ = self.strings.splice(i..i, [left, styled, right].into_iter().filter(|s| !s.content.is_empty()));
+    }
+
+    /// Joins neighbouring spans that share a style.
+    fn normalize(&mut self) {
+        for s in std::mem::take(&mut self.strings) {
+            match self.strings.last_mut() {
+                Some(last) if last.style == s.style => last.content.push_str(&s.content),
+                _ if s.content.is_empty() => {}
+                _ => self.strings.push(s),
+            }
+        }
     }
 
-    pub(crate) fn drain_with_styled_cursor(&mut self, color: Color) -> Vec<Styled<String>> {
-        let cursor_pos = self.get_cursor_pos();
+    fn append(&mut self, s: &str, style: SpanStyle) {
+        match self.strings.last_mut() {
+            Some(text) if text.style == style => text.content.push_str(s),
+            _ => self.strings.push(Span { content: s.to_string(), style }),
+        }
+    }
+
+    /// Writes `s` at the cursor like a terminal would: characters overwrite
+    /// what is under the cursor, `\r` returns to the first column and `\n`
+    /// moves to the next line.
+    fn write_text(&mut self, s: &str) {
+        if self.cursor_pos.is_none() && !s.contains('\r') {
+            self.append(s, self.style.into());
+            return;
+        }
+        let mut pos = self.get_cursor_pos();
+        for c in s.chars() {
+            match c {
+                '\r' => pos.column = 0,
+                '\n' => {
+                    pos.line += 1;
+                    pos.column = 0;
+                }
+                c => {
+                    let styled = Span { content: c.to_string(), style: self.style.into() };
+                    match self.slot(pos) {
+                        Slot::Replace(i, b) => {
+                            let content = &self.strings[i].content;
+                            let len = content[b..].chars().next().map_or(0, char::len_utf8);
+                            self.splice(i, b..b + len, styled)
+                        }
+                        Slot::Insert(i, b) => self.splice(i, b..b, styled),
+                        Slot::Append => self.append(&styled.content, styled.style),
+                    }
+                    pos.column += 1;
+                }
+            }
+        }
+        self.normalize();
+        self.set_cursor_pos(pos);
+    }
+
+    pub(crate) fn drain_with_styled_cursor(&mut self) -> Vec<Span> {
+        let pos = self.get_cursor_pos();
+        let mut slot = self.slot(pos);
+        if let Slot::Insert(i, b) = slot {
+            // The newline is not printed, so put a blank under the cursor.
+            self.splice(i, b..b, Span::new(" ".to_string()));
+            slot = self.slot(pos);
+        }
+        if self.strings.is_empty() {
+            self.strings.push(Span { content: String::new(), style: self.style.into() });
+        }
+        let (index, len) = match slot {
+            Slot::Replace(i, b) => (i, self.strings[i].content[..b].chars().count()),
+            _ => (self.strings.len() - 1, self.strings.last().unwrap().content.chars().count()),
+        };
         let mut strings = std::mem::take(&mut self.strings);
-        let styled_string = std::mem::replace(&mut strings[cursor_pos.index], Styled::new(String::new()));
+        let styled_string = std::mem::replace(&mut strings[index], Span::new(String::new()));
 
         // eprintln!("cursor {:?} str len {}", cursor_pos, styled_string.s.len());
-        let _ = strings.splice(cursor_pos.index..cursor_pos.index + 1, cursorify(styled_string, cursor_pos.len, color));
+        let _ = strings.splice(index..index + 1, cursorify(styled_string, len));
         strings
     }
 
-    fn render(
+    /// Commits what has been written so far as the frame to render.
+    pub(crate) fn commit(&mut self) {
+        let cursor = self.state.cursor_visible.then(|| self.get_cursor_pos());
+        let spans = if self.state.cursor_visible {
+            self.clone().drain_with_styled_cursor()
+        } else {
+            self.strings.clone()
+        };
+        self.show(Frame { spans, cursor });
+    }
+
+    /// Makes `frame` the frame to render.
+    pub(crate) fn show(&mut self, frame: Frame) {
+        self.frame = frame.spans;
+        self.frame_cursor = frame.cursor;
+        self.selection = None;
+        self.dirty = true;
+    }
+
+    /// The last committed frame.
+    pub(crate) fn last_frame(&self) -> Frame {
+        Frame {
+            spans: self.frame.clone(),
+            cursor: self.frame_cursor,
+        }
+    }
+
+    /// The selected text of the last flushed frame.
+    pub(crate) fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection?;
+        Some(
+            with_positions(&self.frame)
+                .filter(|(pos, _, _)| (start..end).contains(pos))
+                .map(|(_, c, _)| c)
+                .collect(),
+        )
+    }
+
+    /// The lines of the last flushed frame, without styles.
+    pub(crate) fn frame_lines(&self) -> Vec<String> {
+        let text: String = self.frame.iter().map(|s| s.content.as_str()).collect();
+        text.split('\n').map(str::to_string).collect()
+    }
+
+    /// The last flushed frame with the selection and preedit drawn in, split
+    /// into lines.
+    pub(crate) fn styled_lines(&self) -> Vec<Vec<Span>> {
+        let mut strings = match self.selection {
+            Some((start, end)) => select(&self.frame, start, end),
+            None => self.frame.clone(),
+        };
+        if let (Some(preedit), Some(cursor)) = (&self.preedit, self.frame_cursor) {
+            let preedit = Span {
+                content: preedit.clone(),
+                style: SpanStyle {
+                    attributes: TextAttributes::UNDERLINE,
+                    ..default()
+                },
+            };
+            strings = insert_at(&strings, cursor, preedit);
+        }
+
+        let mut next_line_count: Option<usize> = None;
+        let mut line_count: usize = 0;
+        let lines = strings
+            .into_iter()
+            .flat_map(|mut s| {
+                let mut a = vec![];
+                let mut b = None;
+                if s.content.contains('\n') {
+                    let str = std::mem::take(&mut s.content);
+                    a.extend(str.split_inclusive('\n').map(move |line| Span {
+                        content: line.to_string(),
+                        ..s.clone()
+                    }));
+                } else {
+                    b = Some(s);
+                }
+                a.into_iter().chain(b)
+            })
+            .group_by(|x| {
+                if let Some(x) = next_line_count.take() {
+                    line_count = x;
+                }
+                if x.content.chars().last().map(|c| c == '\n').unwrap_or(false) {
+                    next_line_count = Some(line_count + 1);
+                }
+                line_count
+            });
+        let mut styled_lines = vec![];
+        for (_, line) in &lines {
+            styled_lines.push(line.collect());
+        }
+        styled_lines
+    }
+
+    /// Draws the last flushed frame as rows of text under `terminal`.
+    ///
+    /// Only lines that changed since the last frame are drawn again, by
+    /// changing the text of their spans if possible; everything is drawn again
+    /// when the cache was invalidated or the palette changed. `advance` is
+    /// the width of a character, used by [RenderMode::Lines].
+    pub(crate) fn render(
         &mut self,
         commands: &mut Commands,
         settings: &BevySettings,
-        column: Entity,
+        palette: &TerminalPalette,
+        terminal: Entity,
+        cache: &mut LineCache,
+        advance: f32,
     ) {
-        // -> io::Result<()>
-        let white = Color::Grey;
+        self.dirty = false;
+        if cache.palette.as_ref() != Some(palette) {
+            commands.entity(terminal).despawn_descendants();
+            cache.lines.clear();
+            cache.palette = Some(palette.clone());
+        }
+        let lines = self.styled_lines();
+        let line_count = lines.len();
+        for (index, line) in lines.into_iter().enumerate() {
+            match cache.lines.get_mut(index) {
+                Some(cached) if same_spans(&cached.spans, &line) => {}
+                Some(cached)
+                    if settings.mode == RenderMode::Spans && can_update(&cached.spans, &line) =>
+                {
+                    update_spans(commands, settings, palette, cached, line);
+                }
+                Some(cached) => {
+                    commands.entity(cached.row).despawn_descendants();
+                    cached.spans =
+                        spawn_row(commands, settings, palette, cached.row, line, advance);
+                }
+                None => {
+                    let row = commands
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Row,
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            TerminalLine {
+                                terminal,
+                                line: index,
+                            },
+                            Interaction::default(),
+                        ))
+                        .id();
+                    commands.entity(terminal).add_child(row);
+                    let spans = spawn_row(commands, settings, palette, row, line, advance);
+                    cache.lines.push(CachedLine { row, spans });
+                }
+            }
+        }
+        for cached in cache.lines.drain(line_count.min(cache.lines.len())..) {
+            commands.entity(cached.row).despawn_recursive();
+        }
+    }
+}
 
-        let strings = if self.state.cursor_visible {
-            self.drain_with_styled_cursor(white)
-        } else {
-            std::mem::take(&mut self.strings)
-        };
+/// The entities drawing a terminal's lines, kept between frames so only the
+/// lines that changed are drawn again.
+#[derive(Component, Default)]
+pub(crate) struct LineCache {
+    palette: Option<TerminalPalette>,
+    lines: Vec<CachedLine>,
+}
 
-        commands.entity(column).with_children(|column| {
-            let mut next_line_count: Option<usize> = None;
-            let mut line_count: usize = 0;
-            let lines = strings
-                .into_iter()
-                .flat_map(|mut s| {
-                    let mut a = vec![];
-                    let mut b = None;
-                    if s.content.contains('\n') {
-                        let str = std::mem::take(&mut s.content);
-                        a.extend(str.split_inclusive('\n').map(move |line| Styled {
-                            content: line.to_string(),
-                            ..s.clone()
-                        }));
-                    } else {
-                        b = Some(s);
-                    }
-                    a.into_iter().chain(b)
-                })
-                .group_by(|x| {
-                    if let Some(x) = next_line_count.take() {
-                        line_count = x;
-                    }
-                    if x.content.chars().last().map(|c| c == '\n').unwrap_or(false) {
-                        next_line_count = Some(line_count + 1);
-                    }
-                    line_count
-                });
+impl LineCache {
+    /// Draws everything again on the next render.
+    pub(crate) fn invalidate(&mut self) {
+        self.palette = None;
+    }
+}
 
-            // let mut line_num = 0;
-            for (_key, line) in &lines {
-                // let style: TextStyleParams = settings.style.clone().into();
-                column
-                    .spawn(NodeBundle {
-                        style: Style {
-                            flex_direction: FlexDirection::Row,
-                            ..default()
-                        },
-                        ..default()
-                    })
-                    .with_children(|parent| {
-                        // if out.state.cursor_visible && line_num == out.state.cursor_pos[1] {
-                        //     text_style::bevy::render_iter(
-                        //         parent,
-                        //         &style,
-                        //         cursorify_iter(line, out.state.cursor_pos[0], white),
-                        //     );
-                        // } else {
-                        render_iter(parent, &settings.style, line);
-                        // }
-                    });
-                // line_num += 1;
+/// A span of each entity drawing it. In [RenderMode::Lines] all spans of a
+/// line share the entity.
+struct CachedLine {
+    row: Entity,
+    spans: Vec<(Entity, Span)>,
+}
+
+fn same_span(a: &Span, b: &Span) -> bool {
+    a.content == b.content && a.style == b.style
+}
+
+fn same_spans(old: &[(Entity, Span)], new: &[Span]) -> bool {
+    old.len() == new.len() && old.iter().zip(new).all(|((_, a), b)| same_span(a, b))
+}
+
+/// Whether `new` can be drawn by changing the text of the entities drawing
+/// `old`. Underlined and struck through spans have extra entities, so those
+/// are spawned again.
+fn can_update(old: &[(Entity, Span)], new: &[Span]) -> bool {
+    let decorated = |s: &Span| s.style.attributes.intersects(DECORATED);
+    old.len() == new.len() && old.iter().zip(new).all(|((_, a), b)| !decorated(a) && !decorated(b))
+}
+
+/// The spans of a line with the columns they start at.
+fn with_columns(line: Vec<Span>) -> impl Iterator<Item = (TerminalSpan, Span)> {
+    let mut column = 0;
+    line.into_iter().map(move |s| {
+        let len = s.content.trim_end_matches('\n').chars().count();
+        let span = TerminalSpan { column, len };
+        column += len;
+        (span, s)
+    })
+}
+
+fn spawn_row(
+    commands: &mut Commands,
+    settings: &BevySettings,
+    palette: &TerminalPalette,
+    row: Entity,
+    line: Vec<Span>,
+    advance: f32,
+) -> Vec<(Entity, Span)> {
+    match settings.mode {
+        // Grid terminals are drawn by `render_grids` instead.
+        RenderMode::Spans | RenderMode::Grid => spawn_spans(commands, settings, palette, row, line),
+        RenderMode::Lines => spawn_line(commands, settings, palette, row, line, advance),
+    }
+}
+
+fn spawn_spans(
+    commands: &mut Commands,
+    settings: &BevySettings,
+    palette: &TerminalPalette,
+    row: Entity,
+    line: Vec<Span>,
+) -> Vec<(Entity, Span)> {
+    let mut spans = vec![];
+    commands.entity(row).with_children(|parent| {
+        for (span, s) in with_columns(line) {
+            let id = render(parent, settings, palette, s.clone())
+                .insert((span, RelativeCursorPosition::default()))
+                .id();
+            spans.push((id, s));
+        }
+    });
+    spans
+}
+
+/// Draws `line` as a single text with a section per span, and quads for
+/// backgrounds and decorations.
+fn spawn_line(
+    commands: &mut Commands,
+    settings: &BevySettings,
+    palette: &TerminalPalette,
+    row: Entity,
+    line: Vec<Span>,
+    advance: f32,
+) -> Vec<(Entity, Span)> {
+    let thickness = (settings.style.font_size / 14.0).max(1.0);
+    let mut sections = vec![];
+    let mut quads = vec![];
+    let mut len = 0;
+    for (span, s) in with_columns(line.clone()) {
+        let (fg, bg) = colors(s.style, palette);
+        let left = Val::Px(span.column as f32 * advance);
+        let width = Val::Px(span.len as f32 * advance);
+        if let Some(bg) = bg {
+            quads.push((left, width, Val::Px(0.0), Val::Percent(100.0), bg));
+        }
+        for (attribute, top) in DECORATIONS {
+            if s.style.attributes.contains(attribute) {
+                quads.push((left, width, top, Val::Px(thickness), fg));
             }
-        });
+        }
+        sections.push(TextSection::new(
+            s.content.trim_end_matches('\n'),
+            TextStyle {
+                font: settings.font(s.style.attributes),
+                color: fg,
+                ..settings.style.clone()
+            },
+        ));
+        len = span.column + span.len;
+    }
+    if len == 0 {
+        // Keeps empty lines a line high.
+        sections.push(TextSection::new(" ", settings.style.clone()));
     }
+    let mut text = row;
+    commands.entity(row).with_children(|parent| {
+        // Backgrounds are spawned first so they are drawn under the text.
+        for (left, width, top, height, color) in quads {
+            parent.spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left,
+                    top,
+                    width,
+                    height,
+                    ..default()
+                },
+                background_color: color.into(),
+                ..default()
+            });
+        }
+        text = parent
+            .spawn((
+                TextBundle::from_sections(sections),
+                TerminalSpan { column: 0, len },
+                RelativeCursorPosition::default(),
+            ))
+            .id();
+    });
+    line.into_iter().map(|s| (text, s)).collect()
 }
 
-pub fn render<'a>(
-    parent: &mut ChildBuilder<'_>,
-    o: &TextStyle,
-    s: impl Into<Styled<String>>,
+fn update_spans(
+    commands: &mut Commands,
+    settings: &BevySettings,
+    palette: &TerminalPalette,
+    cached: &mut CachedLine,
+    line: Vec<Span>,
 ) {
-    parent.spawn(with_style_string(s.into(), o));
+    for ((id, old), (span, s)) in cached.spans.iter_mut().zip(with_columns(line)) {
+        if same_span(old, &s) {
+            // Earlier spans may have changed length.
+            commands.entity(*id).insert(span);
+            continue;
+        }
+        let bundle = with_style_string(s.clone(), settings, palette);
+        commands
+            .entity(*id)
+            .insert((bundle.text, bundle.background_color, span));
+        *old = s;
+    }
 }
 
-pub fn render_iter<'a, I, Iter, S>(
-    parent: &mut ChildBuilder<'_>,
-    o: &TextStyle,
-    iter: I,
-) where
-    I: IntoIterator<Item = S, IntoIter = Iter>,
-    Iter: Iterator<Item = S>,
-    S: Into<Styled<String>>,
-{
-    iter.into_iter().for_each(|b| render(parent, o, b));
+/// Attributes drawn as lines over the text, and how far down they are drawn.
+const DECORATIONS: [(TextAttributes, Val); 2] = [
+    (TextAttributes::UNDERLINE, Val::Percent(90.0)),
+    (TextAttributes::STRIKETHROUGH, Val::Percent(50.0)),
+];
+
+/// All attributes in [DECORATIONS].
+const DECORATED: TextAttributes = TextAttributes::UNDERLINE.union(TextAttributes::STRIKETHROUGH);
+
+pub fn render<'b>(
+    parent: &'b mut ChildBuilder<'_>,
+    settings: &BevySettings,
+    palette: &TerminalPalette,
+    s: Span,
+) -> EntityCommands<'b> {
+    let attributes = s.style.attributes;
+    let text = with_style_string(s, settings, palette);
+    if !attributes.intersects(DECORATED) {
+        return parent.spawn(text);
+    }
+    // Lines are thin nodes laid over the text, so the text is wrapped in a
+    // node they can be positioned in.
+    let color = text.text.sections[0].style.color;
+    let thickness = (text.text.sections[0].style.font_size / 14.0).max(1.0);
+    let mut span = parent.spawn(NodeBundle::default());
+    span.with_children(|span| {
+        span.spawn(text);
+        for (attribute, top) in DECORATIONS {
+            if attributes.contains(attribute) {
+                span.spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(0.0),
+                        top,
+                        width: Val::Percent(100.0),
+                        height: Val::Px(thickness),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                });
+            }
+        }
+    });
+    span
 }
 
 // I originally had this function too:
@@ -164,48 +759,68 @@ pub fn render_iter<'a, I, Iter, S>(
 //
 // but TextBundle requires a String, so Into<StyledString> seemed more explicit.
 fn with_style_string(
-    s: impl Into<Styled<String>>,
-    text_style: &TextStyle,
+    s: Span,
+    settings: &BevySettings,
+    palette: &TerminalPalette,
 ) -> TextBundle {
-    let s = s.into();
+    let text_style = &settings.style;
+    let attributes = s.style.attributes;
+    let (fg, bg) = colors(s.style, palette);
     let bundle = TextBundle::from_section(
         s.content,
-        s.style.fg.map(|fg| TextStyle {
-            color: from_color(fg),
+        TextStyle {
+            font: settings.font(attributes),
+            color: fg,
             ..text_style.clone()
-        }).unwrap_or(text_style.clone())
+        },
     );
-    let bg: Option<BevyColor> = s.style.bg.map(from_color);
     match bg {
         None => bundle,
         Some(color) => bundle.with_background_color(color),
     }
 }
 
-fn from_color(color: Color) -> BevyColor {
+/// Text and background colors of `style`.
+pub(crate) fn colors(style: SpanStyle, palette: &TerminalPalette) -> (BevyColor, Option<BevyColor>) {
+    let attributes = style.attributes;
+    let mut fg = style.fg.map(|c| from_color(c, palette)).unwrap_or(palette.foreground);
+    let mut bg: Option<BevyColor> = style.bg.map(|c| from_color(c, palette));
+    if attributes.contains(TextAttributes::REVERSE) {
+        (fg, bg) = (bg.unwrap_or(palette.background), Some(fg));
+    }
+    if style.cursor {
+        (fg, bg) = (palette.background, Some(palette.cursor));
+    }
+    if attributes.contains(TextAttributes::DIM) {
+        fg = fg.with_a(fg.a() * 0.5);
+    }
+    (fg, bg)
+}
+
+fn from_color(color: Color, palette: &TerminalPalette) -> BevyColor {
     use inquire::ui::Color::*;
 
-    let (r, g, b) = match color {
-        Black => (0, 0, 0),
-        DarkRed => (170, 0, 0),
-        DarkGreen => (0, 170, 0),
-        DarkYellow => (170, 85, 0),
-        DarkBlue => (0, 0, 170),
-        DarkMagenta => (170, 0, 170),
-        DarkCyan => (0, 170, 170),
-        DarkWhite => (170, 170, 170),
-        LightBlack => (85, 85, 85),
-        LightRed => (255, 85, 85),
-        LightGreen => (85, 255, 85),
-        LightYellow => (255, 255, 85),
-        LightBlue => (85, 85, 255),
-        LightMagenta => (255, 85, 255),
-        LightCyan => (85, 255, 255),
-        LightWhite => (255, 255, 255),
-        Rgb { r, g, b } => (r, g, b),
-        _ => todo!(),
+    let n = match color {
+        Black => 0,
+        DarkRed => 1,
+        DarkGreen => 2,
+        DarkYellow => 3,
+        DarkBlue => 4,
+        DarkMagenta => 5,
+        DarkCyan => 6,
+        DarkWhite => 7,
+        LightBlack => 8,
+        LightRed => 9,
+        LightGreen => 10,
+        LightYellow => 11,
+        LightBlue => 12,
+        LightMagenta => 13,
+        LightCyan => 14,
+        LightWhite => 15,
+        AnsiValue(n) => n,
+        Rgb { r, g, b } => return BevyColor::rgb_u8(r, g, b),
     };
-    BevyColor::rgb_u8(r, g, b)
+    palette.ansi_value(n)
 }
 
 // fn no_cursorify(
@@ -216,22 +831,23 @@ fn from_color(color: Color) -> BevyColor {
 //     std::iter::once(cs)
 // }
 
-/// Splits Styled<String> into possibly three pieces: (left string portion, the
+/// Splits a Span into possibly three pieces: (left string portion, the
 /// cursor, right string portion). The character index `i`'s range is not the
 /// usual _[0, N)_ where _N_ is the character count; it is _[0,N]_ inclusive so
-/// that a cursor may be specified essentially at the end of the strin g.
+/// that a cursor may be specified essentially at the end of the strin g. The
+/// cursor is marked with [SpanStyle::cursor] and drawn with the palette's
+/// cursor color.
 fn cursorify(
-    cs: Styled<String>,
+    cs: Span,
     i: usize,
-    cursor_color: Color,
-) -> impl Iterator<Item = Styled<String>> {
+) -> impl Iterator<Item = Span> {
     let (string, style) = (cs.content, cs.style);
     assert!(i <= string.chars().count(),
             "i {} <= str.chars().count() {}", i, string.chars().count());
     let (mut input, right) = match string.char_indices().nth(i + 1) {
         Some((byte_index, _char)) => {
             let (l, r) = string.split_at(byte_index);
-            (l.to_owned(),Some(Styled { content: r.to_owned(), style }))
+            (l.to_owned(),Some(Span { content: r.to_owned(), style }))
         },
         None => {
             let mut s = string;
@@ -242,7 +858,7 @@ fn cursorify(
         }
     };
     let cursor = Some(
-        Styled {
+        Span {
             content: input
                 .pop()
             // Newline is not printed. So use a space if necessary.
@@ -250,36 +866,73 @@ fn cursorify(
             // .unwrap()//_or(' ')
                 .expect("Could not get cursor position")
                 .to_string(),
-            style: style.with_bg(cursor_color)
+            style: SpanStyle { cursor: true, ..style }
         }
     );
-    let left = Some(Styled { content: input, style });
+    let left = Some(Span { content: input, style });
     left.into_iter().chain(cursor.into_iter().chain(right))
 }
 
+/// Every character of `strings` with its position and style.
+fn with_positions(strings: &[Span]) -> impl Iterator<Item = (CursorPos, char, SpanStyle)> + '_ {
+    let mut pos = CursorPos::default();
+    strings
+        .iter()
+        .flat_map(|s| s.content.chars().map(move |c| (c, s.style)))
+        .map(move |(c, style)| {
+            let at = pos;
+            if c == '\n' {
+                pos.line += 1;
+                pos.column = 0;
+            } else {
+                pos.column += 1;
+            }
+            (at, c, style)
+        })
+}
+
+/// Draws the characters from `start` up to `end` in reverse.
+fn select(strings: &[Span], start: CursorPos, end: CursorPos) -> Vec<Span> {
+    let mut selected: Vec<Span> = vec![];
+    for (pos, c, mut style) in with_positions(strings) {
+        if (start..end).contains(&pos) {
+            style.attributes ^= TextAttributes::REVERSE;
+        }
+        match selected.last_mut() {
+            Some(last) if last.style == style => last.content.push(c),
+            _ => selected.push(Span { content: c.to_string(), style }),
+        }
+    }
+    selected
+}
+
+/// Puts `styled` before the character at `at`, or at the end.
+fn insert_at(strings: &[Span], at: CursorPos, styled: Span) -> Vec<Span> {
+    let mut pending = Some(styled);
+    let mut inserted: Vec<Span> = vec![];
+    for (pos, c, style) in with_positions(strings) {
+        if pos == at {
+            inserted.extend(pending.take());
+        }
+        match inserted.last_mut() {
+            Some(last) if last.style == style => last.content.push(c),
+            _ => inserted.push(Span { content: c.to_string(), style }),
+        }
+    }
+    inserted.extend(pending);
+    inserted
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct RendererState {
     // pub(crate) draw_time: DrawTime,
     pub(crate) cursor_visible: bool,
-    pub(crate) newline_count: u16,
 }
 
 impl std::io::Write for StyledStringWriter {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         let s = std::str::from_utf8(buf).expect("Not a utf8 string");
-        let ss = match self.strings.pop() {
-            None => Styled { content: s.to_string(), style: self.style },
-            Some(mut text) => {
-                if text.style == self.style {
-                    text.content.push_str(s);
-                    text
-                } else {
-                    self.strings.push(text);
-                    Styled { content: s.to_string(), style: self.style }
-                }
-            }
-        };
-        self.strings.push(ss);
+        self.write_text(s);
         Ok(buf.len())
     }
 
@@ -290,19 +943,7 @@ impl std::io::Write for StyledStringWriter {
 
 impl std::fmt::Write for StyledStringWriter {
     fn write_str(&mut self, s: &str) -> Result<(), fmt::Error> {
-        let ss = match self.strings.pop() {
-            None => Styled { content: s.to_string(), style: self.style },
-            Some(mut text) => {
-                if text.style == self.style {
-                    text.content.push_str(s);
-                    text
-                } else {
-                    self.strings.push(text);
-                    Styled { content: s.to_string(), style: self.style }
-                }
-            }
-        };
-        self.strings.push(ss);
+        self.write_text(s);
         Ok(())
     }
 }
@@ -496,3 +1137,97 @@ impl std::fmt::Write for StyledStringWriter {
 //         }
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn text(w: &StyledStringWriter) -> String {
+        w.strings.iter().map(|s| s.content.as_str()).collect()
+    }
+
+    #[test]
+    fn overwrites_after_cursor_up() {
+        let mut w = StyledStringWriter::default();
+        write!(w, "abc\ndef").unwrap();
+        w.cursor_up(1);
+        w.cursor_to_column(1);
+        write!(w, "X").unwrap();
+        assert_eq!(text(&w), "aXc\ndef");
+        assert_eq!(w.get_cursor_pos(), CursorPos { line: 0, column: 2 });
+    }
+
+    #[test]
+    fn pads_past_the_end_of_a_line() {
+        let mut w = StyledStringWriter::default();
+        write!(w, "ab\ncd").unwrap();
+        w.cursor_up(1);
+        w.cursor_to_column(4);
+        write!(w, "X").unwrap();
+        assert_eq!(text(&w), "ab  X\ncd");
+    }
+
+    #[test]
+    fn pads_past_the_end_of_the_text() {
+        let mut w = StyledStringWriter::default();
+        write!(w, "ab").unwrap();
+        w.cursor_down(1);
+        write!(w, "X").unwrap();
+        assert_eq!(text(&w), "ab\n  X");
+    }
+
+    #[test]
+    fn overwrites_multi_byte_chars() {
+        let mut w = StyledStringWriter::default();
+        write!(w, "a\u{f1}b").unwrap();
+        w.cursor_left(2);
+        write!(w, "x").unwrap();
+        assert_eq!(text(&w), "axb");
+        w.cursor_left(1);
+        write!(w, "\u{2502}").unwrap();
+        assert_eq!(text(&w), "a\u{2502}b");
+    }
+
+    #[test]
+    fn carriage_return_overwrites_the_line() {
+        let mut w = StyledStringWriter::default();
+        write!(w, "abc\rX").unwrap();
+        assert_eq!(text(&w), "Xbc");
+        assert_eq!(w.get_cursor_pos(), CursorPos { line: 0, column: 1 });
+    }
+
+    #[test]
+    fn clear_until_new_line_keeps_the_start_of_the_line() {
+        let mut w = StyledStringWriter::default();
+        write!(w, "abc\ndef").unwrap();
+        w.cursor_up(1);
+        w.cursor_to_column(1);
+        w.clear_until_new_line();
+        assert_eq!(text(&w), "a\ndef");
+        assert_eq!(w.get_cursor_pos(), CursorPos { line: 0, column: 1 });
+    }
+
+    #[test]
+    fn clear_line_blanks_a_middle_line() {
+        let mut w = StyledStringWriter::default();
+        write!(w, "abc\ndef\nghi").unwrap();
+        w.cursor_up(1);
+        w.clear_line();
+        assert_eq!(text(&w), "abc\n\nghi");
+        assert_eq!(w.get_cursor_pos(), CursorPos { line: 1, column: 3 });
+        write!(w, "X").unwrap();
+        assert_eq!(text(&w), "abc\n   X\nghi");
+    }
+
+    #[test]
+    fn selected_text_spans_lines() {
+        let mut w = StyledStringWriter::default();
+        write!(w, "hello\nworld").unwrap();
+        w.commit();
+        assert_eq!(w.selected_text(), None);
+        w.selection = Some((CursorPos { line: 0, column: 1 }, CursorPos { line: 1, column: 2 }));
+        assert_eq!(w.selected_text().as_deref(), Some("ello\nwo"));
+    }
+}
@@ -0,0 +1,253 @@
+use bevy::prelude::*;
+use inquire::ui::{Key, KeyModifiers};
+
+use crate::focus::TerminalFocus;
+use crate::palette::TerminalPalette;
+use crate::text_style_adapter::BevySettings;
+use crate::BevyInput;
+
+/// Touch input on terminals.
+///
+/// Taps act as clicks, see [MouseSettings](crate::MouseSettings), once the
+/// finger is lifted without moving `swipe_distance` away. Swiping up or down
+/// on a terminal scrolls it a page.
+#[derive(Resource, Debug, Clone)]
+pub struct TouchSettings {
+    pub enabled: bool,
+    /// Shortest vertical distance, in logical pixels, a touch has to move to
+    /// count as a swipe.
+    pub swipe_distance: f32,
+}
+
+impl Default for TouchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            swipe_distance: 60.0,
+        }
+    }
+}
+
+pub(crate) fn swipe_to_keys(
+    settings: Res<TouchSettings>,
+    touches: Res<Touches>,
+    mut terminals: Query<(&Node, &GlobalTransform, &mut BevyInput)>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    for touch in touches.iter_just_released() {
+        let distance = touch.distance();
+        if distance.y.abs() < settings.swipe_distance || distance.y.abs() < distance.x.abs() {
+            continue;
+        }
+        let start = touch.start_position();
+        let Some((_, _, mut input)) = terminals
+            .iter_mut()
+            .find(|(node, transform, _)| node.logical_rect(transform).contains(start))
+        else {
+            continue;
+        };
+        // Swiping up drags the content up, showing the next page.
+        input.keys.push_back(if distance.y < 0.0 {
+            Key::PageDown(KeyModifiers::NONE)
+        } else {
+            Key::PageUp(KeyModifiers::NONE)
+        });
+    }
+}
+
+/// An on-screen keyboard for devices without a physical one.
+///
+/// Spawn it on a UI node, or on an empty entity to get a column node, and its
+/// keys are added as children. Tapping or clicking a key sends it to
+/// `terminal`, or to the focused terminal if that's `None`.
+///
+/// ```ignore
+/// commands.spawn(OnScreenKeyboard::default());
+/// ```
+#[derive(Component, Debug, Clone, Default)]
+pub struct OnScreenKeyboard {
+    pub terminal: Option<Entity>,
+    shifted: bool,
+}
+
+impl OnScreenKeyboard {
+    pub fn for_terminal(terminal: Entity) -> Self {
+        Self {
+            terminal: Some(terminal),
+            ..default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Char(char),
+    Shift,
+    Key(Key),
+}
+
+/// A key of an [OnScreenKeyboard].
+#[derive(Component, Debug, Clone, Copy)]
+pub(crate) struct OnScreenKey {
+    keyboard: Entity,
+    action: Action,
+}
+
+const ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm,.-"];
+
+fn bottom_rows() -> [Vec<(&'static str, Action)>; 2] {
+    let none = KeyModifiers::NONE;
+    [
+        vec![
+            ("Shift", Action::Shift),
+            ("Tab", Action::Key(Key::Tab)),
+            ("Space", Action::Char(' ')),
+            ("Bksp", Action::Key(Key::Backspace)),
+        ],
+        vec![
+            ("Esc", Action::Key(Key::Escape)),
+            ("<", Action::Key(Key::Left(none))),
+            ("^", Action::Key(Key::Up(none))),
+            ("v", Action::Key(Key::Down(none))),
+            (">", Action::Key(Key::Right(none))),
+            ("Enter", Action::Key(Key::Enter)),
+        ],
+    ]
+}
+
+fn label(action: Action, shifted: bool) -> Option<String> {
+    match action {
+        Action::Char(' ') => None,
+        Action::Char(c) if shifted => Some(c.to_ascii_uppercase().to_string()),
+        Action::Char(c) => Some(c.to_string()),
+        _ => None,
+    }
+}
+
+pub(crate) fn build_on_screen_keyboards(
+    mut commands: Commands,
+    settings: Res<BevySettings>,
+    palette: Res<TerminalPalette>,
+    keyboards: Query<(Entity, Has<Node>), Added<OnScreenKeyboard>>,
+) {
+    for (id, has_node) in &keyboards {
+        if !has_node {
+            commands.entity(id).insert(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            });
+        }
+        let rows = ROWS
+            .iter()
+            .map(|row| row.chars().map(|c| (None, Action::Char(c))).collect::<Vec<_>>())
+            .chain(bottom_rows().map(|row| {
+                row.into_iter()
+                    .map(|(name, action)| (Some(name), action))
+                    .collect::<Vec<_>>()
+            }));
+        commands.entity(id).with_children(|keyboard| {
+            for row in rows {
+                keyboard
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row_node| {
+                        for (name, action) in row {
+                            let text = name
+                                .map(str::to_string)
+                                .or_else(|| label(action, false))
+                                .unwrap_or_default();
+                            row_node
+                                .spawn((
+                                    ButtonBundle {
+                                        style: Style {
+                                            margin: UiRect::all(Val::Px(2.0)),
+                                            padding: UiRect::axes(Val::Px(10.0), Val::Px(6.0)),
+                                            justify_content: JustifyContent::Center,
+                                            ..default()
+                                        },
+                                        background_color: palette.ansi[8].into(),
+                                        ..default()
+                                    },
+                                    OnScreenKey {
+                                        keyboard: id,
+                                        action,
+                                    },
+                                ))
+                                .with_children(|button| {
+                                    button.spawn(TextBundle::from_section(
+                                        text,
+                                        TextStyle {
+                                            color: palette.foreground,
+                                            ..settings.style.clone()
+                                        },
+                                    ));
+                                });
+                        }
+                    });
+            }
+        });
+    }
+}
+
+pub(crate) fn press_on_screen_keys(
+    focus: Res<TerminalFocus>,
+    pressed: Query<(&Interaction, &OnScreenKey), Changed<Interaction>>,
+    mut keyboards: Query<&mut OnScreenKeyboard>,
+    mut inputs: Query<&mut BevyInput>,
+) {
+    for (interaction, key) in &pressed {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Ok(mut keyboard) = keyboards.get_mut(key.keyboard) else {
+            continue;
+        };
+        let key = match key.action {
+            Action::Shift => {
+                keyboard.shifted = !keyboard.shifted;
+                continue;
+            }
+            Action::Char(c) if keyboard.shifted => {
+                keyboard.shifted = false;
+                Key::Char(c.to_ascii_uppercase(), KeyModifiers::SHIFT)
+            }
+            Action::Char(c) => Key::Char(c, KeyModifiers::NONE),
+            Action::Key(key) => key,
+        };
+        let terminal = keyboard.terminal.or(focus.0);
+        if let Some(mut input) = terminal.and_then(|id| inputs.get_mut(id).ok()) {
+            input.keys.push_back(key);
+        }
+    }
+}
+
+/// Shows upper case letters while the keyboard is shifted.
+pub(crate) fn relabel_on_screen_keys(
+    keyboards: Query<&OnScreenKeyboard, Changed<OnScreenKeyboard>>,
+    keys: Query<(&OnScreenKey, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (key, children) in &keys {
+        let Ok(keyboard) = keyboards.get(key.keyboard) else {
+            continue;
+        };
+        let Some(label) = label(key.action, keyboard.shifted) else {
+            continue;
+        };
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = label.clone();
+        }
+    }
+}